@@ -0,0 +1,185 @@
+//! Validates every material toml under a directory, for CI.
+//!
+//! `validate_materials [--json] [--template <shader template>] [directory]`
+//!
+//! WGSL is validated against the sprite template shipped with the editor, or the engine's own template
+//! given with `--template` or the `MATERIAL_SHADER_TEMPLATE` environment variable.
+//! The directory defaults to the materials shipped in `./binaries/`.
+//!
+//! Checks uniform types, texture descs, the blend state and the WGSL snippets of each material,
+//! prints a report per file and exits with 1 if any material is broken.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use material_editor_gui::{
+    material::{self, MaterialSections},
+    shader,
+};
+use serde::Serialize;
+
+static DEFAULT_MATERIAL_DIRECTORY: &str = "./binaries/";
+static SHADER_TEMPLATE_VAR: &str = "MATERIAL_SHADER_TEMPLATE";
+
+#[derive(Serialize)]
+struct MaterialReport {
+    path: PathBuf,
+    errors: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct Report {
+    materials: Vec<MaterialReport>,
+    failed: usize,
+}
+
+struct Options {
+    json: bool,
+    template: Option<PathBuf>,
+    directory: PathBuf,
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut options = Options {
+        json: false,
+        template: env::var_os(SHADER_TEMPLATE_VAR).map(PathBuf::from),
+        directory: PathBuf::from(DEFAULT_MATERIAL_DIRECTORY),
+    };
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => options.json = true,
+            "--template" => {
+                options.template = Some(PathBuf::from(
+                    args.next().ok_or("--template needs a file path")?,
+                ))
+            }
+            _ if arg.starts_with("--") => return Err(format!("unknown option {arg}")),
+            _ => options.directory = PathBuf::from(arg),
+        }
+    }
+
+    Ok(options)
+}
+
+fn validate_material(path: &Path, template: &str) -> Vec<String> {
+    let material_toml = match fs::read_to_string(path) {
+        Ok(material_toml) => material_toml,
+        Err(error) => return vec![format!("Failed to read material: {error}")],
+    };
+    if let Err(error) = material_toml.parse::<toml::Table>() {
+        return vec![format!("Invalid toml: {}", error.message())];
+    }
+
+    let (sections, blend_error) = MaterialSections::from_toml(&material_toml);
+    let mut errors = sections.validate();
+    if let Some(error) = blend_error {
+        errors.push(format!("Blend State: {error}"));
+    }
+
+    // Snippet errors already explain what is wrong, naga would only repeat them less clearly
+    if errors.is_empty() {
+        if let Err(error) = shader::validate_wgsl(&sections, template) {
+            errors.push(format!("WGSL: {error}"));
+        }
+    }
+
+    errors
+}
+
+fn main() -> ExitCode {
+    let options = match parse_options() {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{error}");
+            eprintln!(
+                "usage: validate_materials [--json] [--template <shader template>] [directory]"
+            );
+            return ExitCode::from(2);
+        }
+    };
+
+    let template = match &options.template {
+        Some(template_path) => {
+            let template = match fs::read_to_string(template_path) {
+                Ok(template) => template,
+                Err(error) => {
+                    eprintln!(
+                        "Failed to read shader template {} with error {error}",
+                        template_path.to_string_lossy()
+                    );
+                    return ExitCode::from(2);
+                }
+            };
+            if let Err(error) = shader::check_template(&template) {
+                eprintln!(
+                    "Invalid shader template {}: {error}",
+                    template_path.to_string_lossy()
+                );
+                return ExitCode::from(2);
+            }
+            template
+        }
+        None => shader::SPRITE_TEMPLATE.to_string(),
+    };
+
+    if !options.directory.is_dir() {
+        eprintln!("{} is not a directory", options.directory.to_string_lossy());
+        return ExitCode::from(2);
+    }
+
+    // Sorted so reports diff cleanly between runs
+    let materials: Vec<MaterialReport> = material::find_material_files(&options.directory)
+        .into_iter()
+        .map(|path| MaterialReport {
+            errors: validate_material(&path, &template),
+            path,
+        })
+        .collect();
+    let report = Report {
+        failed: materials
+            .iter()
+            .filter(|material| !material.errors.is_empty())
+            .count(),
+        materials,
+    };
+
+    if options.json {
+        match serde_json::to_string_pretty(&report) {
+            Ok(json) => println!("{json}"),
+            Err(error) => {
+                eprintln!("Failed to serialize report with error {error}");
+                return ExitCode::from(2);
+            }
+        }
+    } else {
+        for material in &report.materials {
+            let status = if material.errors.is_empty() {
+                "ok"
+            } else {
+                "FAILED"
+            };
+            println!("{status:<8}{}", material.path.to_string_lossy());
+            for error in &material.errors {
+                for line in error.lines() {
+                    println!("        {line}");
+                }
+            }
+        }
+        println!(
+            "{} materials, {} failed",
+            report.materials.len(),
+            report.failed
+        );
+    }
+
+    if report.failed > 0 {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
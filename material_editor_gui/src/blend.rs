@@ -0,0 +1,490 @@
+use eframe::egui::{self, ComboBox};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum BlendMode {
+    #[default]
+    Alpha,
+    Premultiplied,
+    Additive,
+    Multiply,
+    Opaque,
+    Custom,
+}
+
+impl BlendMode {
+    pub const ALL: [BlendMode; 6] = [
+        BlendMode::Alpha,
+        BlendMode::Premultiplied,
+        BlendMode::Additive,
+        BlendMode::Multiply,
+        BlendMode::Opaque,
+        BlendMode::Custom,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BlendMode::Alpha => "alpha",
+            BlendMode::Premultiplied => "premultiplied",
+            BlendMode::Additive => "additive",
+            BlendMode::Multiply => "multiply",
+            BlendMode::Opaque => "opaque",
+            BlendMode::Custom => "custom",
+        }
+    }
+
+    /// Color and alpha components of the preset modes, same as the matching `wgpu::BlendState` constants.
+    pub fn preset(&self) -> Option<(BlendComponent, BlendComponent)> {
+        use BlendFactor::*;
+
+        match self {
+            BlendMode::Alpha => Some((
+                BlendComponent::new(SrcAlpha, OneMinusSrcAlpha),
+                BlendComponent::new(One, OneMinusSrcAlpha),
+            )),
+            BlendMode::Premultiplied => Some((
+                BlendComponent::new(One, OneMinusSrcAlpha),
+                BlendComponent::new(One, OneMinusSrcAlpha),
+            )),
+            BlendMode::Additive => Some((
+                BlendComponent::new(SrcAlpha, One),
+                BlendComponent::new(Zero, One),
+            )),
+            BlendMode::Multiply => Some((
+                BlendComponent::new(Dst, Zero),
+                BlendComponent::new(Zero, One),
+            )),
+            BlendMode::Opaque => Some((
+                BlendComponent::new(One, Zero),
+                BlendComponent::new(One, Zero),
+            )),
+            BlendMode::Custom => None,
+        }
+    }
+}
+
+/// Names match `wgpu::BlendFactor` in snake case, which is what the module parses.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum BlendFactor {
+    Zero,
+    One,
+    Src,
+    OneMinusSrc,
+    SrcAlpha,
+    OneMinusSrcAlpha,
+    Dst,
+    OneMinusDst,
+    DstAlpha,
+    OneMinusDstAlpha,
+    SrcAlphaSaturated,
+}
+
+impl BlendFactor {
+    pub const ALL: [BlendFactor; 11] = [
+        BlendFactor::Zero,
+        BlendFactor::One,
+        BlendFactor::Src,
+        BlendFactor::OneMinusSrc,
+        BlendFactor::SrcAlpha,
+        BlendFactor::OneMinusSrcAlpha,
+        BlendFactor::Dst,
+        BlendFactor::OneMinusDst,
+        BlendFactor::DstAlpha,
+        BlendFactor::OneMinusDstAlpha,
+        BlendFactor::SrcAlphaSaturated,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BlendFactor::Zero => "zero",
+            BlendFactor::One => "one",
+            BlendFactor::Src => "src",
+            BlendFactor::OneMinusSrc => "one_minus_src",
+            BlendFactor::SrcAlpha => "src_alpha",
+            BlendFactor::OneMinusSrcAlpha => "one_minus_src_alpha",
+            BlendFactor::Dst => "dst",
+            BlendFactor::OneMinusDst => "one_minus_dst",
+            BlendFactor::DstAlpha => "dst_alpha",
+            BlendFactor::OneMinusDstAlpha => "one_minus_dst_alpha",
+            BlendFactor::SrcAlphaSaturated => "src_alpha_saturated",
+        }
+    }
+}
+
+/// Names match `wgpu::BlendOperation` in snake case.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum BlendOperation {
+    Add,
+    Subtract,
+    ReverseSubtract,
+    Min,
+    Max,
+}
+
+impl BlendOperation {
+    pub const ALL: [BlendOperation; 5] = [
+        BlendOperation::Add,
+        BlendOperation::Subtract,
+        BlendOperation::ReverseSubtract,
+        BlendOperation::Min,
+        BlendOperation::Max,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BlendOperation::Add => "add",
+            BlendOperation::Subtract => "subtract",
+            BlendOperation::ReverseSubtract => "reverse_subtract",
+            BlendOperation::Min => "min",
+            BlendOperation::Max => "max",
+        }
+    }
+}
+
+/// `result = src * src_factor (operation) dst * dst_factor`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct BlendComponent {
+    pub src_factor: BlendFactor,
+    pub dst_factor: BlendFactor,
+    pub operation: BlendOperation,
+}
+
+impl BlendComponent {
+    const fn new(src_factor: BlendFactor, dst_factor: BlendFactor) -> Self {
+        Self {
+            src_factor,
+            dst_factor,
+            operation: BlendOperation::Add,
+        }
+    }
+
+    fn from_toml(value: &toml::Value) -> Result<Self, String> {
+        let table = value
+            .as_table()
+            .ok_or("expected a table with src, dst and operation".to_string())?;
+        let field = |key: &str| {
+            table
+                .get(key)
+                .and_then(|value| value.as_str())
+                .ok_or(format!("missing `{key}`"))
+        };
+
+        let src_factor = parse_factor(field("src")?)?;
+        let dst_factor = parse_factor(field("dst")?)?;
+        let operation = match table.get("operation") {
+            Some(_) => {
+                let operation = field("operation")?;
+                BlendOperation::ALL
+                    .into_iter()
+                    .find(|candidate| candidate.name() == operation)
+                    .ok_or(format!("unknown blend operation `{operation}`"))?
+            }
+            None => BlendOperation::Add,
+        };
+
+        Ok(Self {
+            src_factor,
+            dst_factor,
+            operation,
+        })
+    }
+
+    fn to_toml(self) -> String {
+        format!(
+            "{{ src = \"{}\", dst = \"{}\", operation = \"{}\" }}",
+            self.src_factor.name(),
+            self.dst_factor.name(),
+            self.operation.name()
+        )
+    }
+}
+
+fn parse_factor(factor: &str) -> Result<BlendFactor, String> {
+    BlendFactor::ALL
+        .into_iter()
+        .find(|candidate| candidate.name() == factor)
+        .ok_or(format!("unknown blend factor `{factor}`"))
+}
+
+/// How a material's fragment color is blended with the render target.
+///
+/// Saved in the material as a `[blend_state]` table, ex: `mode = "additive"`.
+/// Custom blending also lists both components,
+/// ex: `color = { src = "src_alpha", dst = "one", operation = "add" }`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct BlendState {
+    pub mode: BlendMode,
+    // Only used by BlendMode::Custom
+    pub color: BlendComponent,
+    pub alpha: BlendComponent,
+}
+
+impl Default for BlendState {
+    fn default() -> Self {
+        let (color, alpha) = BlendMode::Alpha.preset().unwrap_or_default();
+        Self {
+            mode: BlendMode::Alpha,
+            color,
+            alpha,
+        }
+    }
+}
+
+impl Default for BlendComponent {
+    fn default() -> Self {
+        Self::new(BlendFactor::One, BlendFactor::Zero)
+    }
+}
+
+impl BlendState {
+    pub fn from_toml(table: &toml::Table) -> Result<Self, String> {
+        let mut blend_state = BlendState::default();
+
+        let mode = table
+            .get("mode")
+            .and_then(|mode| mode.as_str())
+            .unwrap_or(BlendMode::Alpha.name());
+        blend_state.mode = BlendMode::ALL
+            .into_iter()
+            .find(|candidate| candidate.name() == mode)
+            .ok_or(format!(
+                "unknown blend mode `{mode}`, expected alpha, premultiplied, additive, multiply, opaque or custom"
+            ))?;
+
+        if blend_state.mode == BlendMode::Custom {
+            for (key, component) in [
+                ("color", &mut blend_state.color),
+                ("alpha", &mut blend_state.alpha),
+            ] {
+                let value = table
+                    .get(key)
+                    .ok_or(format!("custom blending needs a `{key}` component"))?;
+                *component =
+                    BlendComponent::from_toml(value).map_err(|error| format!("{key}: {error}"))?;
+            }
+        }
+
+        Ok(blend_state)
+    }
+
+    pub fn to_toml(self) -> String {
+        let mut toml = format!("mode = \"{}\"", self.mode.name());
+        if self.mode == BlendMode::Custom {
+            toml += &format!(
+                "\ncolor = {}\nalpha = {}",
+                self.color.to_toml(),
+                self.alpha.to_toml()
+            );
+        }
+        toml
+    }
+
+    /// The color and alpha components the pipeline is built with.
+    pub fn components(&self) -> (BlendComponent, BlendComponent) {
+        self.mode.preset().unwrap_or((self.color, self.alpha))
+    }
+
+    /// Sent with compiles as `color_src color_dst color_operation alpha_src alpha_dst alpha_operation`.
+    pub fn command_arg(&self) -> String {
+        let (color, alpha) = self.components();
+        [color, alpha]
+            .iter()
+            .flat_map(|component| {
+                [
+                    component.src_factor.name(),
+                    component.dst_factor.name(),
+                    component.operation.name(),
+                ]
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Min and max ignore the blend factors, wgpu requires them to be `one`.
+    pub fn warning(&self) -> Option<&'static str> {
+        let (color, alpha) = self.components();
+        [color, alpha]
+            .iter()
+            .any(|component| {
+                matches!(
+                    component.operation,
+                    BlendOperation::Min | BlendOperation::Max
+                ) && (component.src_factor != BlendFactor::One
+                    || component.dst_factor != BlendFactor::One)
+            })
+            .then_some("min and max blending use `one` for both factors")
+    }
+}
+
+/// Mode picker, with factor and operation pickers for custom blending.
+pub fn blend_editor(ui: &mut egui::Ui, blend_state: &mut BlendState) {
+    ui.horizontal(|ui| {
+        ui.label("Blend");
+        let previous_mode = blend_state.mode;
+        ComboBox::from_id_salt("blend_mode")
+            .selected_text(blend_state.mode.name())
+            .show_ui(ui, |ui| {
+                for mode in BlendMode::ALL {
+                    ui.selectable_value(&mut blend_state.mode, mode, mode.name());
+                }
+            });
+
+        // Custom blending starts from whatever the previous preset did
+        if blend_state.mode == BlendMode::Custom && previous_mode != BlendMode::Custom {
+            (blend_state.color, blend_state.alpha) = previous_mode.preset().unwrap_or_default();
+        }
+
+        if let Some(warning) = blend_state.warning() {
+            ui.colored_label(ui.visuals().warn_fg_color, warning);
+        }
+    });
+
+    if blend_state.mode == BlendMode::Custom {
+        egui::Grid::new("blend_components")
+            .num_columns(4)
+            .show(ui, |ui| {
+                for (label, component) in [
+                    ("Color", &mut blend_state.color),
+                    ("Alpha", &mut blend_state.alpha),
+                ] {
+                    ui.label(label);
+                    for (field, factor) in [
+                        ("src", &mut component.src_factor),
+                        ("dst", &mut component.dst_factor),
+                    ] {
+                        ComboBox::from_id_salt(format!("blend_{label}_{field}"))
+                            .selected_text(format!("{field}: {}", factor.name()))
+                            .show_ui(ui, |ui| {
+                                for candidate in BlendFactor::ALL {
+                                    ui.selectable_value(factor, candidate, candidate.name());
+                                }
+                            });
+                    }
+                    ComboBox::from_id_salt(format!("blend_{label}_operation"))
+                        .selected_text(component.operation.name())
+                        .show_ui(ui, |ui| {
+                            for operation in BlendOperation::ALL {
+                                ui.selectable_value(
+                                    &mut component.operation,
+                                    operation,
+                                    operation.name(),
+                                );
+                            }
+                        });
+                    ui.end_row();
+                }
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::MaterialSections;
+
+    fn parse(toml_text: &str) -> Result<BlendState, String> {
+        BlendState::from_toml(&toml_text.parse::<toml::Table>().unwrap())
+    }
+
+    #[test]
+    fn preset_modes_round_trip() {
+        for mode in BlendMode::ALL
+            .into_iter()
+            .filter(|mode| *mode != BlendMode::Custom)
+        {
+            let blend_state = BlendState {
+                mode,
+                ..Default::default()
+            };
+            assert_eq!(parse(&blend_state.to_toml()), Ok(blend_state));
+        }
+    }
+
+    #[test]
+    fn custom_components_round_trip() {
+        let blend_state = BlendState {
+            mode: BlendMode::Custom,
+            color: BlendComponent {
+                src_factor: BlendFactor::DstAlpha,
+                dst_factor: BlendFactor::OneMinusSrc,
+                operation: BlendOperation::ReverseSubtract,
+            },
+            alpha: BlendComponent::new(BlendFactor::One, BlendFactor::One),
+        };
+        let toml_text = blend_state.to_toml();
+
+        assert!(toml_text.contains("operation = \"reverse_subtract\""));
+        assert_eq!(parse(&toml_text), Ok(blend_state));
+    }
+
+    #[test]
+    fn missing_operation_defaults_to_add() {
+        let blend_state = parse(
+            "mode = \"custom\"\ncolor = { src = \"src_alpha\", dst = \"one\" }\nalpha = { src = \"zero\", dst = \"one\" }",
+        )
+        .unwrap();
+
+        assert_eq!(blend_state.color.operation, BlendOperation::Add);
+        assert_eq!(blend_state.alpha.operation, BlendOperation::Add);
+    }
+
+    #[test]
+    fn invalid_blend_states_are_errors() {
+        assert!(parse("mode = \"screen\"").is_err());
+        assert!(parse("mode = \"custom\"\ncolor = { src = \"one\", dst = \"zero\" }").is_err());
+        assert!(
+            parse(
+                "mode = \"custom\"\ncolor = { src = \"one\", dst = \"half\" }\nalpha = { src = \"one\", dst = \"zero\" }"
+            )
+            .is_err()
+        );
+        assert_eq!(parse(""), Ok(BlendState::default()));
+    }
+
+    #[test]
+    fn command_arg_lists_the_pipeline_components() {
+        assert_eq!(
+            BlendState::default().command_arg(),
+            "src_alpha one_minus_src_alpha add one one_minus_src_alpha add"
+        );
+
+        let additive = BlendState {
+            mode: BlendMode::Additive,
+            ..Default::default()
+        };
+        assert_eq!(additive.command_arg(), "src_alpha one add zero one add");
+
+        // Components saved from an earlier custom mode don't apply to presets
+        let opaque = BlendState {
+            mode: BlendMode::Opaque,
+            color: BlendComponent::new(BlendFactor::Dst, BlendFactor::Src),
+            alpha: BlendComponent::new(BlendFactor::Dst, BlendFactor::Src),
+        };
+        assert_eq!(opaque.command_arg(), "one zero add one zero add");
+    }
+
+    #[test]
+    fn default_blend_is_left_out_of_saved_materials() {
+        let sections = MaterialSections {
+            frag_color: "return vec4f(1.);".to_string(),
+            ..Default::default()
+        };
+        let saved = sections.to_toml();
+        assert!(!saved.contains("[blend_state]"));
+        assert_eq!(
+            MaterialSections::from_toml(&saved).0.blend,
+            BlendState::default()
+        );
+
+        let sections = MaterialSections {
+            blend: BlendState {
+                mode: BlendMode::Multiply,
+                ..Default::default()
+            },
+            ..sections
+        };
+        let saved = sections.to_toml();
+        assert!(saved.contains("[blend_state]\nmode = \"multiply\""));
+        assert_eq!(MaterialSections::from_toml(&saved), (sections, None));
+    }
+}
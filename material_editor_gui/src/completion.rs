@@ -0,0 +1,266 @@
+use eframe::egui::{
+    self, Id, Key, Modifiers, RichText,
+    text::{CCursor, CCursorRange},
+    text_edit::{TextEditOutput, TextEditState},
+};
+
+use crate::highlight::{Symbols, WGSL_BUILTINS};
+
+static MAX_SUGGESTIONS: usize = 12;
+
+// Parameters the engine's template passes to both snippets
+static SNIPPET_PARAMETERS: &[(&str, &str)] = &[("uv0", "vec4f")];
+
+#[derive(Debug, Clone, PartialEq)]
+struct Suggestion {
+    insert: String,
+    detail: String,
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+fn byte_index(text: &str, char_index: usize) -> usize {
+    text.char_indices()
+        .nth(char_index)
+        .map_or(text.len(), |(byte_index, _)| byte_index)
+}
+
+fn suggestions(before_word: &str, prefix: &str, symbols: &Symbols) -> Vec<Suggestion> {
+    let suggestion = |insert: &str, detail: String| Suggestion {
+        insert: insert.to_string(),
+        detail,
+    };
+
+    // Only uniforms can follow `scene_instance.`
+    let mut suggestions: Vec<Suggestion> = if before_word
+        .strip_suffix("scene_instance.")
+        .is_some_and(|rest| !rest.ends_with(is_identifier_char))
+    {
+        symbols
+            .uniforms
+            .iter()
+            .map(|(name, uniform_type)| suggestion(name, uniform_type.clone()))
+            .collect()
+    } else {
+        let textures = symbols.textures.iter().map(|name| {
+            let detail = if name.starts_with("sampler_") {
+                "sampler"
+            } else {
+                "texture_2d<f32>"
+            };
+            suggestion(name, detail.to_string())
+        });
+        let parameters = SNIPPET_PARAMETERS
+            .iter()
+            .map(|(name, parameter_type)| suggestion(name, parameter_type.to_string()));
+        let builtins = WGSL_BUILTINS
+            .iter()
+            .map(|(name, signature)| suggestion(name, signature.to_string()));
+
+        [suggestion("scene_instance", "uniforms".to_string())]
+            .into_iter()
+            .chain(parameters)
+            .chain(textures)
+            .chain(builtins)
+            .collect()
+    };
+
+    suggestions
+        .retain(|suggestion| suggestion.insert.starts_with(prefix) && suggestion.insert != prefix);
+    suggestions.truncate(MAX_SUGGESTIONS);
+    suggestions
+}
+
+/// Completion popup for the snippet editors, suggesting declared uniforms, textures,
+/// snippet parameters and WGSL builtins for the word being typed.
+#[derive(Default)]
+pub struct Autocomplete {
+    // Editor the popup belongs to
+    editor: Option<Id>,
+    suggestions: Vec<Suggestion>,
+    selected: usize,
+    // Char range of the word being completed
+    word_start: usize,
+    word_end: usize,
+    // Where the popup was drawn, so clicking it doesn't close it when the editor loses focus
+    popup_rect: Option<egui::Rect>,
+}
+
+impl Autocomplete {
+    fn close(&mut self) {
+        self.editor = None;
+        self.suggestions.clear();
+        self.popup_rect = None;
+    }
+
+    fn is_open(&self, editor_id: Id) -> bool {
+        self.editor == Some(editor_id) && !self.suggestions.is_empty()
+    }
+
+    /// Call before showing the editor, so the open popup gets arrow keys, enter and tab instead of the text.
+    pub fn handle_keys(&mut self, ctx: &egui::Context, editor_id: Id, text: &mut String) {
+        if !self.is_open(editor_id) {
+            return;
+        }
+
+        let (down, up, accept, close) = ctx.input_mut(|input| {
+            (
+                input.consume_key(Modifiers::NONE, Key::ArrowDown),
+                input.consume_key(Modifiers::NONE, Key::ArrowUp),
+                input.consume_key(Modifiers::NONE, Key::Enter)
+                    || input.consume_key(Modifiers::NONE, Key::Tab),
+                input.consume_key(Modifiers::NONE, Key::Escape),
+            )
+        });
+
+        let count = self.suggestions.len();
+        if down {
+            self.selected = (self.selected + 1) % count;
+        }
+        if up {
+            self.selected = (self.selected + count - 1) % count;
+        }
+        if accept {
+            self.accept(ctx, editor_id, text);
+        }
+        if close {
+            self.close();
+        }
+    }
+
+    /// Call after showing the editor, updates the suggestions for the word at the cursor and draws the popup.
+    pub fn show(
+        &mut self,
+        ui: &egui::Ui,
+        editor_id: Id,
+        text: &mut String,
+        output: &TextEditOutput,
+        symbols: &Symbols,
+    ) {
+        let pointer_on_popup = self.editor == Some(editor_id)
+            && ui
+                .ctx()
+                .pointer_latest_pos()
+                .zip(self.popup_rect)
+                .is_some_and(|(pointer, popup_rect)| popup_rect.contains(pointer));
+        let cursor_range = output.cursor_range.filter(|_| output.response.has_focus());
+        let popup_pos = match cursor_range {
+            Some(cursor_range) => {
+                let cursor = cursor_range.primary.ccursor.index;
+
+                // Ctrl+Space opens the popup without typing
+                let forced =
+                    ui.input_mut(|input| input.consume_key(Modifiers::COMMAND, Key::Space));
+                if output.response.changed() || forced {
+                    self.update(editor_id, text, cursor, symbols, forced);
+                } else if self.editor == Some(editor_id) && cursor != self.word_end {
+                    self.close();
+                }
+
+                output
+                    .galley
+                    .pos_from_cursor(&cursor_range.primary)
+                    .translate(output.galley_pos.to_vec2())
+                    .left_bottom()
+            }
+            None if pointer_on_popup => match self.popup_rect {
+                Some(popup_rect) => popup_rect.left_top(),
+                None => return,
+            },
+            None => {
+                if self.editor == Some(editor_id) {
+                    self.close();
+                }
+                return;
+            }
+        };
+        if !self.is_open(editor_id) {
+            return;
+        }
+
+        let mut clicked = None;
+        let area = egui::Area::new(editor_id.with("autocomplete"))
+            .order(egui::Order::Foreground)
+            .fixed_pos(popup_pos)
+            .show(ui.ctx(), |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    for (index, suggestion) in self.suggestions.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            if ui
+                                .selectable_label(
+                                    index == self.selected,
+                                    RichText::new(&suggestion.insert).monospace(),
+                                )
+                                .clicked()
+                            {
+                                clicked = Some(index);
+                            }
+                            ui.weak(&suggestion.detail);
+                        });
+                    }
+                });
+            });
+        self.popup_rect = Some(area.response.rect);
+
+        if let Some(index) = clicked {
+            self.selected = index;
+            self.accept(ui.ctx(), editor_id, text);
+            ui.ctx()
+                .memory_mut(|memory| memory.request_focus(editor_id));
+        }
+    }
+
+    fn update(
+        &mut self,
+        editor_id: Id,
+        text: &str,
+        cursor: usize,
+        symbols: &Symbols,
+        forced: bool,
+    ) {
+        let cursor_byte = byte_index(text, cursor);
+        let word_start_byte = text[..cursor_byte]
+            .rfind(|c: char| !is_identifier_char(c))
+            .map_or(0, |index| index + 1);
+        let before_word = &text[..word_start_byte];
+        let prefix = &text[word_start_byte..cursor_byte];
+
+        // Suggest while typing a word, or straight after `scene_instance.`
+        let after_uniforms = before_word.ends_with("scene_instance.");
+        if prefix.is_empty() && !after_uniforms && !forced
+            || prefix.starts_with(|c: char| c.is_ascii_digit())
+        {
+            self.close();
+            return;
+        }
+
+        let suggestions = suggestions(before_word, prefix, symbols);
+        if self.editor != Some(editor_id) || suggestions != self.suggestions {
+            self.selected = 0;
+        }
+        self.editor = Some(editor_id);
+        self.suggestions = suggestions;
+        self.word_start = cursor - prefix.chars().count();
+        self.word_end = cursor;
+    }
+
+    fn accept(&mut self, ctx: &egui::Context, editor_id: Id, text: &mut String) {
+        let Some(suggestion) = self.suggestions.get(self.selected) else {
+            return;
+        };
+
+        let start_byte = byte_index(text, self.word_start);
+        let end_byte = byte_index(text, self.word_end);
+        text.replace_range(start_byte..end_byte, &suggestion.insert);
+
+        // Put the cursor after the inserted word
+        let cursor = CCursor::new(self.word_start + suggestion.insert.chars().count());
+        let mut state = TextEditState::load(ctx, editor_id).unwrap_or_default();
+        state.cursor.set_char_range(Some(CCursorRange::one(cursor)));
+        state.store(ctx, editor_id);
+
+        self.close();
+    }
+}
@@ -0,0 +1,213 @@
+use std::{
+    collections::BTreeMap,
+    env,
+    path::PathBuf,
+    time::{Instant, SystemTime},
+};
+
+use crate::{
+    blend::BlendState,
+    material::MaterialSections,
+    undo::{EditorSnapshot, UndoHistory},
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DirtySections {
+    pub uniforms: bool,
+    pub textures: bool,
+    pub world_offset: bool,
+    pub frag_color: bool,
+    pub blend: bool,
+    pub texture_assignments: bool,
+}
+
+impl DirtySections {
+    pub fn any(&self) -> bool {
+        !self.names().is_empty()
+    }
+
+    /// Names of the modified sections, ex: `Uniforms, Blend`.
+    pub fn names(&self) -> Vec<&'static str> {
+        [
+            ("Uniforms", self.uniforms),
+            ("Textures", self.textures),
+            ("World Offset", self.world_offset),
+            ("Fragment Color", self.frag_color),
+            ("Blend", self.blend),
+            ("Texture Assignments", self.texture_assignments),
+        ]
+        .into_iter()
+        .filter_map(|(name, dirty)| dirty.then_some(name))
+        .collect()
+    }
+}
+
+/// A material open in its own tab, with its own sections, texture assignments and undo history.
+pub struct MaterialDocument {
+    // Keeps the editor widgets of different tabs apart
+    pub id: u64,
+
+    pub shader_path: PathBuf,
+    // Sections as last loaded from or saved to shader_path, and the file's modified time at that point
+    pub saved_sections: MaterialSections,
+    pub shader_modified: Option<SystemTime>,
+    pub last_file_poll: Instant,
+    // Sections changed on disk while the document had unsaved edits, waiting for the user to pick a side
+    pub disk_conflict: Option<MaterialSections>,
+
+    // Texture file assigned to each [texture_descs] binding, keyed by binding name.
+    // Relative to the asset root when the texture lies inside the asset tree
+    pub texture_assignments: BTreeMap<String, PathBuf>,
+    pub saved_texture_assignments: BTreeMap<String, PathBuf>,
+
+    pub textures_text: String,
+    pub uniforms_text: String,
+    pub world_offset_text: String,
+    pub frag_color_text: String,
+    pub blend: BlendState,
+
+    // Sections seen last frame, live mode compiles once they stop changing
+    pub last_sections: MaterialSections,
+    pub last_edit: Option<Instant>,
+    pub validation_errors: Vec<String>,
+    // Why the file's [blend_state] couldn't be read when it was loaded, alpha blending is used instead
+    pub blend_error: Option<String>,
+
+    pub undo_history: UndoHistory,
+
+    // Preview sprite the engine shows this document on while its tab is active
+    pub preview_entity: usize,
+}
+
+impl MaterialDocument {
+    pub fn new(id: u64, preview_entity: usize) -> Self {
+        Self {
+            id,
+            shader_path: env::current_dir().unwrap_or("./".into()),
+            saved_sections: MaterialSections::default(),
+            shader_modified: None,
+            last_file_poll: Instant::now(),
+            disk_conflict: None,
+            texture_assignments: BTreeMap::new(),
+            saved_texture_assignments: BTreeMap::new(),
+            textures_text: String::new(),
+            uniforms_text: String::new(),
+            world_offset_text: String::new(),
+            frag_color_text: String::new(),
+            blend: BlendState::default(),
+            last_sections: MaterialSections::default(),
+            last_edit: None,
+            validation_errors: Vec::new(),
+            blend_error: None,
+            undo_history: UndoHistory::default(),
+            preview_entity,
+        }
+    }
+
+    // New documents point at the working directory until a file is loaded into them
+    pub fn is_untitled(&self) -> bool {
+        self.shader_path.extension().is_none()
+    }
+
+    /// Tab title, the file name with a `*` while there are unsaved edits.
+    pub fn title(&self) -> String {
+        let file_name = if self.is_untitled() {
+            "untitled".to_string()
+        } else {
+            self.shader_path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string()
+        };
+
+        if self.has_unsaved_edits() {
+            format!("{file_name}*")
+        } else {
+            file_name
+        }
+    }
+
+    // Materials are registered in the engine under the name of the file being edited
+    pub fn material_name(&self) -> String {
+        if self.is_untitled() {
+            return "untitled".to_string();
+        }
+
+        self.shader_path
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect()
+    }
+
+    pub fn sections(&self) -> MaterialSections {
+        MaterialSections {
+            uniforms: self.uniforms_text.clone(),
+            textures: self.textures_text.clone(),
+            world_offset: self.world_offset_text.clone(),
+            frag_color: self.frag_color_text.clone(),
+            blend: self.blend,
+        }
+    }
+
+    pub fn set_sections(&mut self, sections: MaterialSections) {
+        self.uniforms_text = sections.uniforms;
+        self.textures_text = sections.textures;
+        self.world_offset_text = sections.world_offset;
+        self.frag_color_text = sections.frag_color;
+        self.blend = sections.blend;
+    }
+
+    /// Which parts of the document differ from what was last loaded or saved.
+    pub fn dirty_sections(&self) -> DirtySections {
+        let sections = self.sections().normalized();
+        DirtySections {
+            uniforms: sections.uniforms != self.saved_sections.uniforms,
+            textures: sections.textures != self.saved_sections.textures,
+            world_offset: sections.world_offset != self.saved_sections.world_offset,
+            frag_color: sections.frag_color != self.saved_sections.frag_color,
+            blend: sections.blend != self.saved_sections.blend,
+            texture_assignments: self.texture_assignments != self.saved_texture_assignments,
+        }
+    }
+
+    pub fn has_unsaved_edits(&self) -> bool {
+        self.dirty_sections().any()
+    }
+
+    pub fn snapshot(&self) -> EditorSnapshot {
+        EditorSnapshot {
+            shader_path: self.shader_path.clone(),
+            sections: self.sections(),
+            texture_assignments: self.texture_assignments.clone(),
+        }
+    }
+
+    /// `compile_id` is echoed back in the module's compile_result.
+    pub fn compile_command(&self, compile_id: u64) -> String {
+        format!(
+            "compile##DELIM##{}\n##DELIM##{}\n##DELIM##{}\n##DELIM##{}\n##DELIM##{}##DELIM##{}##DELIM##{compile_id}##DELIM##",
+            self.uniforms_text
+                .replace("\r", "\n")
+                .trim_start()
+                .trim_end(),
+            self.textures_text
+                .replace("\r", "\n")
+                .trim_start()
+                .trim_end(),
+            self.world_offset_text
+                .replace("\r", "\n")
+                .trim_start()
+                .trim_end(),
+            self.frag_color_text
+                .replace("\r", "\n")
+                .trim_start()
+                .trim_end(),
+            self.material_name(),
+            self.blend.command_arg(),
+        )
+    }
+}
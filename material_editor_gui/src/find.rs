@@ -0,0 +1,667 @@
+use std::{ops::Range, path::PathBuf};
+
+use eframe::egui::{
+    self, Id, RichText, ScrollArea, TextEdit,
+    text::{CCursor, CCursorRange},
+    text_edit::TextEditState,
+};
+use regex::{Regex, RegexBuilder};
+
+use crate::{document::MaterialDocument, material};
+
+// More matches than this are counted but not listed
+static MAX_LISTED_MATCHES: usize = 200;
+
+/// The four text sections of a material, each edited in its own text box.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    Uniforms,
+    Textures,
+    WorldOffset,
+    FragColor,
+}
+
+impl Section {
+    pub const ALL: [Section; 4] = [
+        Section::Uniforms,
+        Section::Textures,
+        Section::WorldOffset,
+        Section::FragColor,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Section::Uniforms => "Uniforms",
+            Section::Textures => "Textures",
+            Section::WorldOffset => "World Offset",
+            Section::FragColor => "Fragment Color",
+        }
+    }
+
+    pub fn text(self, document: &MaterialDocument) -> &String {
+        match self {
+            Section::Uniforms => &document.uniforms_text,
+            Section::Textures => &document.textures_text,
+            Section::WorldOffset => &document.world_offset_text,
+            Section::FragColor => &document.frag_color_text,
+        }
+    }
+
+    pub fn text_mut(self, document: &mut MaterialDocument) -> &mut String {
+        match self {
+            Section::Uniforms => &mut document.uniforms_text,
+            Section::Textures => &mut document.textures_text,
+            Section::WorldOffset => &mut document.world_offset_text,
+            Section::FragColor => &mut document.frag_color_text,
+        }
+    }
+
+    /// Id of the section's text box, one per document so tabs keep their own cursor and scroll.
+    pub fn editor_id(self, document_id: u64) -> Id {
+        let id_salt = match self {
+            Section::Uniforms => "uniforms_editor",
+            Section::Textures => "textures_editor",
+            Section::WorldOffset => "world_offset_editor",
+            Section::FragColor => "fragment_color_editor",
+        };
+        Id::new((id_salt, document_id))
+    }
+}
+
+// Selects `range` (in bytes) in the section's text box and focuses it
+fn select_in_editor(ctx: &egui::Context, editor_id: Id, text: &str, range: Range<usize>) {
+    let start = text[..range.start].chars().count();
+    let end = start + text[range].chars().count();
+
+    let mut state = TextEditState::load(ctx, editor_id).unwrap_or_default();
+    state.cursor.set_char_range(Some(CCursorRange::two(
+        CCursor::new(start),
+        CCursor::new(end),
+    )));
+    state.store(ctx, editor_id);
+    ctx.memory_mut(|memory| memory.request_focus(editor_id));
+}
+
+struct FindMatch {
+    section: Section,
+    range: Range<usize>,
+    line_number: usize,
+    line: String,
+}
+
+/// Find and replace across the sections of the active material.
+pub struct FindReplace {
+    pub is_open: bool,
+    focus_query: bool,
+    query: String,
+    replacement: String,
+    use_regex: bool,
+    match_case: bool,
+    whole_word: bool,
+    sections: [bool; 4],
+}
+
+impl Default for FindReplace {
+    fn default() -> Self {
+        Self {
+            is_open: false,
+            focus_query: false,
+            query: String::new(),
+            replacement: String::new(),
+            use_regex: false,
+            match_case: false,
+            whole_word: false,
+            sections: [true; 4],
+        }
+    }
+}
+
+impl FindReplace {
+    /// Opens the window with the query box focused, searching for `query` when given.
+    pub fn open(&mut self, query: Option<String>) {
+        self.is_open = true;
+        self.focus_query = true;
+        if let Some(query) = query {
+            self.query = query;
+        }
+    }
+
+    // Plain queries are escaped, so the same regex does the finding and replacing in both modes
+    fn pattern(&self) -> Result<Regex, regex::Error> {
+        let mut pattern = if self.use_regex {
+            self.query.clone()
+        } else {
+            regex::escape(&self.query)
+        };
+        if self.whole_word {
+            pattern = format!(r"\b(?:{pattern})\b");
+        }
+        RegexBuilder::new(&pattern)
+            .case_insensitive(!self.match_case)
+            .build()
+    }
+
+    fn find_matches(&self, pattern: &Regex, document: &MaterialDocument) -> Vec<FindMatch> {
+        Section::ALL
+            .into_iter()
+            .zip(self.sections)
+            .filter(|(_, searched)| *searched)
+            .flat_map(|(section, _)| {
+                let text = section.text(document);
+                pattern
+                    .find_iter(text)
+                    .filter(|found| !found.is_empty())
+                    .map(move |found| {
+                        let line_start = text[..found.start()]
+                            .rfind('\n')
+                            .map_or(0, |index| index + 1);
+                        let line_end = text[found.start()..]
+                            .find('\n')
+                            .map_or(text.len(), |index| found.start() + index);
+                        FindMatch {
+                            section,
+                            range: found.range(),
+                            line_number: text[..found.start()].matches('\n').count() + 1,
+                            line: text[line_start..line_end].trim().to_string(),
+                        }
+                    })
+            })
+            .collect()
+    }
+
+    // `$1` style group references are expanded in regex mode, plain replacements are inserted as is
+    fn replacement_for(&self, pattern: &Regex, text: &str, range: &Range<usize>) -> String {
+        if !self.use_regex {
+            return self.replacement.clone();
+        }
+
+        let mut replacement = String::new();
+        if let Some(captures) = pattern.captures_at(text, range.start) {
+            captures.expand(&self.replacement, &mut replacement);
+        }
+        replacement
+    }
+
+    /// Replacements are made on the document directly and become a single undo step. Returns a
+    /// status message after replacing.
+    pub fn show(&mut self, ctx: &egui::Context, document: &mut MaterialDocument) -> Option<String> {
+        if !self.is_open {
+            return None;
+        }
+
+        let mut status = None;
+        let mut is_open = self.is_open;
+        egui::Window::new("Find and Replace")
+            .open(&mut is_open)
+            .default_width(420.)
+            .show(ctx, |ui| {
+                egui::Grid::new("find_replace_fields")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Find:");
+                        let query_box = ui.add(
+                            TextEdit::singleline(&mut self.query).desired_width(f32::INFINITY),
+                        );
+                        if std::mem::take(&mut self.focus_query) {
+                            query_box.request_focus();
+                        }
+                        ui.end_row();
+
+                        ui.label("Replace:");
+                        ui.add(
+                            TextEdit::singleline(&mut self.replacement)
+                                .desired_width(f32::INFINITY),
+                        );
+                        ui.end_row();
+                    });
+
+                ui.horizontal_wrapped(|ui| {
+                    ui.checkbox(&mut self.use_regex, "Regex")
+                        .on_hover_text("Replacements can refer to groups as $1 or ${name}");
+                    ui.checkbox(&mut self.match_case, "Match case");
+                    ui.checkbox(&mut self.whole_word, "Whole word");
+                });
+                ui.horizontal_wrapped(|ui| {
+                    ui.label("In:");
+                    for (section, searched) in Section::ALL.into_iter().zip(&mut self.sections) {
+                        ui.checkbox(searched, section.name());
+                    }
+                });
+                ui.separator();
+
+                if self.query.is_empty() {
+                    ui.weak("Type something to find");
+                    return;
+                }
+                let pattern = match self.pattern() {
+                    Ok(pattern) => pattern,
+                    Err(error) => {
+                        ui.colored_label(ui.visuals().error_fg_color, error.to_string());
+                        return;
+                    }
+                };
+
+                let matches = self.find_matches(&pattern, document);
+                let mut replaced = None;
+                ui.horizontal(|ui| {
+                    ui.label(format!("{} matches", matches.len()));
+                    if ui
+                        .add_enabled(!matches.is_empty(), egui::Button::new("Replace All"))
+                        .clicked()
+                    {
+                        replaced = Some(None);
+                    }
+                });
+
+                ScrollArea::vertical().max_height(300.).show(ui, |ui| {
+                    for (index, find_match) in matches.iter().enumerate().take(MAX_LISTED_MATCHES) {
+                        ui.horizontal(|ui| {
+                            if ui.small_button("Replace").clicked() {
+                                replaced = Some(Some(index));
+                            }
+                            let location =
+                                format!("{}:{}", find_match.section.name(), find_match.line_number);
+                            if ui
+                                .selectable_label(false, RichText::new(location).weak())
+                                .on_hover_text("Select in the editor")
+                                .clicked()
+                            {
+                                select_in_editor(
+                                    ctx,
+                                    find_match.section.editor_id(document.id),
+                                    find_match.section.text(document),
+                                    find_match.range.clone(),
+                                );
+                            }
+                            ui.label(RichText::new(&find_match.line).monospace());
+                        });
+                    }
+                    if matches.len() > MAX_LISTED_MATCHES {
+                        ui.weak(format!(
+                            "{} more not listed",
+                            matches.len() - MAX_LISTED_MATCHES
+                        ));
+                    }
+                });
+
+                // Replaced back to front so the ranges of earlier matches stay valid
+                let replaced_matches: Vec<&FindMatch> = match replaced {
+                    None => return,
+                    Some(None) => matches.iter().rev().collect(),
+                    Some(Some(index)) => vec![&matches[index]],
+                };
+                // Expanded against the text before any replacement, like a single replace_all would
+                let replacements: Vec<String> = replaced_matches
+                    .iter()
+                    .map(|find_match| {
+                        self.replacement_for(
+                            &pattern,
+                            find_match.section.text(document),
+                            &find_match.range,
+                        )
+                    })
+                    .collect();
+                let binding_names = |document: &MaterialDocument| -> Vec<String> {
+                    material::parse_texture_bindings(&document.textures_text)
+                        .into_iter()
+                        .map(|binding| binding.name)
+                        .collect()
+                };
+                let old_binding_names = binding_names(document);
+                for (find_match, replacement) in replaced_matches.iter().zip(&replacements) {
+                    find_match
+                        .section
+                        .text_mut(document)
+                        .replace_range(find_match.range.clone(), replacement);
+                }
+                rekey_texture_assignments(document, &old_binding_names, &binding_names(document));
+                document
+                    .undo_history
+                    .label_next_change(format!("Replace `{}`", self.query));
+                status = Some(format!(
+                    "Replaced {} occurrences of `{}`",
+                    replaced_matches.len(),
+                    self.query
+                ));
+            });
+
+        self.is_open = is_open;
+        status
+    }
+}
+
+// Replacing in the textures section can rename bindings, their textures follow them by slot.
+// Bindings added or removed leave no way to tell which slot became which, assignments are kept
+fn rekey_texture_assignments(
+    document: &mut MaterialDocument,
+    old_names: &[String],
+    new_names: &[String],
+) {
+    if old_names.len() != new_names.len() {
+        return;
+    }
+
+    // Taken out before reinserting so swapped names don't overwrite each other
+    let renamed: Vec<(&String, PathBuf)> = old_names
+        .iter()
+        .zip(new_names)
+        .filter(|(old_name, new_name)| old_name != new_name)
+        .filter_map(|(old_name, new_name)| {
+            Some((new_name, document.texture_assignments.remove(old_name)?))
+        })
+        .collect();
+    for (new_name, texture_path) in renamed {
+        document
+            .texture_assignments
+            .insert(new_name.clone(), texture_path);
+    }
+}
+
+/// Renames a uniform or texture in its declaration and every snippet that refers to it.
+#[derive(Default)]
+pub struct RenameSymbol {
+    pub is_open: bool,
+    focus_name: bool,
+    old_name: String,
+    new_name: String,
+    error: Option<String>,
+}
+
+impl RenameSymbol {
+    /// Opens the window, renaming `symbol` when it is a declared uniform or texture.
+    pub fn open(&mut self, symbol: Option<String>) {
+        let symbol = symbol.unwrap_or_default();
+        *self = Self {
+            is_open: true,
+            focus_name: true,
+            new_name: symbol.clone(),
+            old_name: symbol,
+            error: None,
+        };
+    }
+
+    /// Returns a status message after renaming.
+    pub fn show(&mut self, ctx: &egui::Context, document: &mut MaterialDocument) -> Option<String> {
+        if !self.is_open {
+            return None;
+        }
+
+        // Samplers are renamed along with their texture
+        let uniform_names: Vec<String> = document
+            .uniforms_text
+            .parse::<toml::Table>()
+            .map(|uniforms| uniforms.keys().cloned().collect())
+            .unwrap_or_default();
+        let texture_names: Vec<String> = material::parse_texture_bindings(&document.textures_text)
+            .into_iter()
+            .map(|binding| binding.name)
+            .collect();
+        if let Some(texture_name) = self.old_name.strip_prefix("sampler_") {
+            if texture_names.iter().any(|name| name == texture_name) {
+                self.old_name = texture_name.to_string();
+                self.new_name = self.old_name.clone();
+            }
+        }
+
+        let mut status = None;
+        let mut is_open = self.is_open;
+        egui::Window::new("Rename Symbol")
+            .open(&mut is_open)
+            .resizable(false)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                let name_entered = egui::Grid::new("rename_symbol_fields")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Symbol:");
+                        egui::ComboBox::from_id_salt("rename_symbol_old_name")
+                            .selected_text(&self.old_name)
+                            .show_ui(ui, |ui| {
+                                for name in uniform_names.iter().chain(&texture_names) {
+                                    if ui
+                                        .selectable_value(&mut self.old_name, name.clone(), name)
+                                        .clicked()
+                                    {
+                                        self.new_name = name.clone();
+                                    }
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label("New name:");
+                        let name_box = ui.text_edit_singleline(&mut self.new_name);
+                        if std::mem::take(&mut self.focus_name) {
+                            name_box.request_focus();
+                        }
+                        ui.end_row();
+
+                        name_box.lost_focus()
+                            && ui.input(|input| input.key_pressed(egui::Key::Enter))
+                    })
+                    .inner;
+
+                if let Some(error) = &self.error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+
+                let rename = ui
+                    .add_enabled(self.old_name != self.new_name, egui::Button::new("Rename"))
+                    .clicked()
+                    || name_entered;
+                if !rename || self.old_name == self.new_name {
+                    return;
+                }
+
+                let mut sections = document.sections();
+                match material::rename_symbol(&mut sections, &self.old_name, &self.new_name) {
+                    Ok(()) => {
+                        document.set_sections(sections);
+                        if let Some(texture_path) =
+                            document.texture_assignments.remove(&self.old_name)
+                        {
+                            document
+                                .texture_assignments
+                                .insert(self.new_name.clone(), texture_path);
+                        }
+                        document.undo_history.label_next_change(format!(
+                            "Rename {} to {}",
+                            self.old_name, self.new_name
+                        ));
+                        status = Some(format!("Renamed {} to {}", self.old_name, self.new_name));
+                        self.is_open = false;
+                    }
+                    Err(error) => self.error = Some(error),
+                }
+            });
+
+        self.is_open &= is_open;
+        status
+    }
+}
+
+/// Identifier under the cursor of whichever section editor has focus.
+pub fn word_at_cursor(ctx: &egui::Context, document: &MaterialDocument) -> Option<String> {
+    let section = Section::ALL
+        .into_iter()
+        .find(|section| ctx.memory(|memory| memory.has_focus(section.editor_id(document.id))))?;
+    let state = TextEditState::load(ctx, section.editor_id(document.id))?;
+    let cursor = state.cursor.char_range()?.primary.index;
+
+    let chars: Vec<char> = section.text(document).chars().collect();
+    let is_identifier_char = |c: &char| c.is_ascii_alphanumeric() || *c == '_';
+    let start = chars[..cursor.min(chars.len())]
+        .iter()
+        .rposition(|c| !is_identifier_char(c))
+        .map_or(0, |index| index + 1);
+    let end = chars[start..]
+        .iter()
+        .position(|c| !is_identifier_char(c))
+        .map_or(chars.len(), |index| start + index);
+
+    (start < end).then(|| chars[start..end].iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document() -> MaterialDocument {
+        let mut document = MaterialDocument::new(0, 0);
+        document.uniforms_text =
+            "tint = { type = \"vec4f\", default = [1.0, 1.0, 1.0, 1.0] }".to_string();
+        document.textures_text = "color_tex = \"linear\"\nmask_tex = \"nearest\"".to_string();
+        document.world_offset_text = "return vec2f(0., 0.);".to_string();
+        document.frag_color_text =
+            "let Tint = scene_instance.tint;\nreturn Tint * textureSample(color_tex, sampler_color_tex, uv0.xy);"
+                .to_string();
+        document
+    }
+
+    fn find_replace(query: &str) -> FindReplace {
+        FindReplace {
+            query: query.to_string(),
+            ..Default::default()
+        }
+    }
+
+    // Section and line of every match
+    fn matches(find_replace: &FindReplace, document: &MaterialDocument) -> Vec<(Section, usize)> {
+        let pattern = find_replace.pattern().unwrap();
+        find_replace
+            .find_matches(&pattern, document)
+            .into_iter()
+            .map(|find_match| (find_match.section, find_match.line_number))
+            .collect()
+    }
+
+    #[test]
+    fn plain_queries_ignore_case_unless_asked() {
+        let document = document();
+
+        assert_eq!(
+            matches(&find_replace("tint"), &document),
+            [
+                (Section::Uniforms, 1),
+                (Section::FragColor, 1),
+                (Section::FragColor, 1),
+                (Section::FragColor, 2),
+            ]
+        );
+        let match_case = FindReplace {
+            match_case: true,
+            ..find_replace("Tint")
+        };
+        assert_eq!(
+            matches(&match_case, &document),
+            [(Section::FragColor, 1), (Section::FragColor, 2)]
+        );
+        // Regex characters in plain queries are matched literally
+        assert!(matches(&find_replace("vec2f(0."), &document).len() == 1);
+    }
+
+    #[test]
+    fn whole_word_skips_longer_identifiers() {
+        let document = document();
+        let whole_word = FindReplace {
+            whole_word: true,
+            ..find_replace("color_tex")
+        };
+
+        assert_eq!(
+            matches(&find_replace("color_tex"), &document),
+            [
+                (Section::Textures, 1),
+                (Section::FragColor, 2),
+                (Section::FragColor, 2),
+            ]
+        );
+        assert_eq!(
+            matches(&whole_word, &document),
+            [(Section::Textures, 1), (Section::FragColor, 2)]
+        );
+    }
+
+    #[test]
+    fn regex_replacements_expand_groups() {
+        let document = document();
+        let group_replace = FindReplace {
+            use_regex: true,
+            replacement: "${name}_map".to_string(),
+            ..find_replace(r"(?P<name>\w+)_tex\b")
+        };
+        let pattern = group_replace.pattern().unwrap();
+
+        let replacements: Vec<String> = group_replace
+            .find_matches(&pattern, &document)
+            .iter()
+            .map(|find_match| {
+                group_replace.replacement_for(
+                    &pattern,
+                    find_match.section.text(&document),
+                    &find_match.range,
+                )
+            })
+            .collect();
+        assert_eq!(
+            replacements,
+            ["color_map", "mask_map", "color_map", "sampler_color_map"]
+        );
+
+        let invalid = FindReplace {
+            use_regex: true,
+            ..find_replace("(unclosed")
+        };
+        assert!(invalid.pattern().is_err());
+    }
+
+    #[test]
+    fn only_checked_sections_are_searched() {
+        let document = document();
+        let textures_only = FindReplace {
+            sections: [false, true, false, false],
+            ..find_replace("tex")
+        };
+
+        assert_eq!(
+            matches(&textures_only, &document),
+            [(Section::Textures, 1), (Section::Textures, 2)]
+        );
+    }
+
+    #[test]
+    fn renamed_bindings_keep_their_textures() {
+        let names =
+            |names: &[&str]| -> Vec<String> { names.iter().map(|name| name.to_string()).collect() };
+        let mut document = document();
+        document
+            .texture_assignments
+            .insert("color_tex".to_string(), "textures/color.png".into());
+        document
+            .texture_assignments
+            .insert("mask_tex".to_string(), "textures/mask.png".into());
+
+        // Swapped names swap their textures rather than losing one
+        rekey_texture_assignments(
+            &mut document,
+            &names(&["color_tex", "mask_tex"]),
+            &names(&["mask_tex", "color_tex"]),
+        );
+        assert_eq!(
+            document.texture_assignments["color_tex"],
+            PathBuf::from("textures/mask.png")
+        );
+        assert_eq!(
+            document.texture_assignments["mask_tex"],
+            PathBuf::from("textures/color.png")
+        );
+
+        // A binding was removed, so which texture goes where is unknown
+        let before = document.texture_assignments.clone();
+        rekey_texture_assignments(
+            &mut document,
+            &names(&["color_tex", "mask_tex"]),
+            &names(&["albedo"]),
+        );
+        assert_eq!(document.texture_assignments, before);
+    }
+}
@@ -0,0 +1,331 @@
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use eframe::egui::{self, ComboBox, DragValue, Grid};
+use image::RgbaImage;
+
+use crate::{MAX_TEXTURES, material::TextureBinding};
+
+static GENERATED_TEXTURES_DIR: &str = "./temp/generated/";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Pattern {
+    Gradient,
+    Checkerboard,
+    UvTest,
+    Noise,
+}
+
+impl Pattern {
+    const ALL: [Pattern; 4] = [
+        Pattern::Gradient,
+        Pattern::Checkerboard,
+        Pattern::UvTest,
+        Pattern::Noise,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Pattern::Gradient => "Gradient",
+            Pattern::Checkerboard => "Checkerboard",
+            Pattern::UvTest => "UV Test",
+            Pattern::Noise => "Noise",
+        }
+    }
+
+    fn file_stem(&self) -> &'static str {
+        match self {
+            Pattern::Gradient => "gradient",
+            Pattern::Checkerboard => "checkerboard",
+            Pattern::UvTest => "uv_test",
+            Pattern::Noise => "noise",
+        }
+    }
+}
+
+/// Generates test textures procedurally and writes them to the temp directory as png.
+pub struct TextureGenerator {
+    pattern: Pattern,
+    width: u32,
+    height: u32,
+    color_a: [f32; 4],
+    color_b: [f32; 4],
+    vertical: bool,
+    cell_size: u32,
+    noise_scale: f32,
+    noise_octaves: u32,
+    seed: u32,
+    target_slot: usize,
+
+    // Appended to file names so the engine never serves a stale cached texture
+    generated_count: u32,
+    // Last texture written for each slot, deleted once a newer one replaces it
+    generated_paths: HashMap<usize, PathBuf>,
+}
+
+impl Default for TextureGenerator {
+    fn default() -> Self {
+        Self {
+            pattern: Pattern::Gradient,
+            width: 256,
+            height: 256,
+            color_a: [0., 0., 0., 1.],
+            color_b: [1., 1., 1., 1.],
+            vertical: false,
+            cell_size: 32,
+            noise_scale: 16.,
+            noise_octaves: 4,
+            seed: 0,
+            target_slot: 0,
+            generated_count: 0,
+            generated_paths: HashMap::new(),
+        }
+    }
+}
+
+impl TextureGenerator {
+    /// Draws the generator settings. Returns the slot and path of a newly generated texture.
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        texture_bindings: &[TextureBinding],
+    ) -> Option<(usize, PathBuf)> {
+        let mut generated = None;
+
+        Grid::new("texture_generator")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Pattern");
+                ComboBox::from_id_salt("generator_pattern")
+                    .selected_text(self.pattern.label())
+                    .show_ui(ui, |ui| {
+                        for pattern in Pattern::ALL {
+                            ui.selectable_value(&mut self.pattern, pattern, pattern.label());
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("Resolution");
+                ui.horizontal(|ui| {
+                    ui.add(DragValue::new(&mut self.width).range(1..=4096));
+                    ui.label("x");
+                    ui.add(DragValue::new(&mut self.height).range(1..=4096));
+                    for size in [64, 256, 1024] {
+                        if ui.small_button(size.to_string()).clicked() {
+                            self.width = size;
+                            self.height = size;
+                        }
+                    }
+                });
+                ui.end_row();
+
+                match self.pattern {
+                    Pattern::Gradient => {
+                        self.color_rows(ui);
+                        ui.label("Direction");
+                        ui.checkbox(&mut self.vertical, "Vertical");
+                        ui.end_row();
+                    }
+                    Pattern::Checkerboard => {
+                        self.color_rows(ui);
+                        ui.label("Cell size");
+                        ui.add(DragValue::new(&mut self.cell_size).range(1..=2048));
+                        ui.end_row();
+                    }
+                    Pattern::UvTest => {
+                        ui.label("Grid cell size");
+                        ui.add(DragValue::new(&mut self.cell_size).range(1..=2048));
+                        ui.end_row();
+                    }
+                    Pattern::Noise => {
+                        self.color_rows(ui);
+                        ui.label("Scale");
+                        ui.add(DragValue::new(&mut self.noise_scale).range(1.0..=1024.0));
+                        ui.end_row();
+                        ui.label("Octaves");
+                        ui.add(DragValue::new(&mut self.noise_octaves).range(1..=8));
+                        ui.end_row();
+                        ui.label("Seed");
+                        ui.horizontal(|ui| {
+                            ui.add(DragValue::new(&mut self.seed));
+                            if ui.small_button("Random").clicked() {
+                                self.seed = hash(self.seed, self.generated_count, 0x9e37_79b9);
+                            }
+                        });
+                        ui.end_row();
+                    }
+                }
+
+                ui.label("Target");
+                // The engine only has MAX_TEXTURES slots, bindings past that are never loaded
+                let slot_count = texture_bindings.len().min(MAX_TEXTURES);
+                self.target_slot = self.target_slot.min(slot_count.saturating_sub(1));
+                ComboBox::from_id_salt("generator_target")
+                    .selected_text(
+                        texture_bindings
+                            .get(self.target_slot)
+                            .map(|binding| binding.name.as_str())
+                            .unwrap_or("<no texture bindings>"),
+                    )
+                    .show_ui(ui, |ui| {
+                        for (slot, binding) in
+                            texture_bindings.iter().enumerate().take(MAX_TEXTURES)
+                        {
+                            ui.selectable_value(&mut self.target_slot, slot, &binding.name);
+                        }
+                    });
+                ui.end_row();
+            });
+
+        if ui
+            .add_enabled(!texture_bindings.is_empty(), egui::Button::new("Generate"))
+            .clicked()
+        {
+            generated = self
+                .write_texture()
+                .map(|texture_path| (self.target_slot, texture_path));
+        }
+
+        generated
+    }
+
+    fn color_rows(&mut self, ui: &mut egui::Ui) {
+        ui.label("Color A");
+        ui.color_edit_button_rgba_unmultiplied(&mut self.color_a);
+        ui.end_row();
+        ui.label("Color B");
+        ui.color_edit_button_rgba_unmultiplied(&mut self.color_b);
+        ui.end_row();
+    }
+
+    fn write_texture(&mut self) -> Option<PathBuf> {
+        let _ = fs::create_dir_all(GENERATED_TEXTURES_DIR);
+        self.generated_count += 1;
+
+        let texture_path = PathBuf::from(GENERATED_TEXTURES_DIR).join(format!(
+            "{}_{}x{}_{}.png",
+            self.pattern.file_stem(),
+            self.width,
+            self.height,
+            self.generated_count
+        ));
+        // The engine resolves textures from its own working directory, so send absolute paths
+        let texture_path = std::path::absolute(&texture_path).unwrap_or(texture_path);
+
+        match self.generate().save(&texture_path) {
+            Ok(()) => {
+                println!("Generated texture {}", texture_path.to_string_lossy());
+                if let Some(superseded_path) = self
+                    .generated_paths
+                    .insert(self.target_slot, texture_path.clone())
+                {
+                    if let Err(error) = fs::remove_file(&superseded_path) {
+                        println!(
+                            "Failed to remove generated texture {} with error {error}",
+                            superseded_path.to_string_lossy()
+                        );
+                    }
+                }
+                Some(texture_path)
+            }
+            Err(error) => {
+                println!(
+                    "Failed to write generated texture {} with error {error}",
+                    texture_path.to_string_lossy()
+                );
+                None
+            }
+        }
+    }
+
+    fn generate(&self) -> RgbaImage {
+        let (width, height) = (self.width as f32, self.height as f32);
+
+        RgbaImage::from_fn(self.width, self.height, |x, y| {
+            let u = (x as f32 + 0.5) / width;
+            let v = (y as f32 + 0.5) / height;
+
+            let color = match self.pattern {
+                Pattern::Gradient => {
+                    mix(self.color_a, self.color_b, if self.vertical { v } else { u })
+                }
+                Pattern::Checkerboard => {
+                    if (x / self.cell_size + y / self.cell_size) % 2 == 0 {
+                        self.color_a
+                    } else {
+                        self.color_b
+                    }
+                }
+                Pattern::UvTest => {
+                    if x % self.cell_size == 0 || y % self.cell_size == 0 {
+                        [1., 1., 1., 1.]
+                    } else {
+                        [u, v, 0., 1.]
+                    }
+                }
+                Pattern::Noise => mix(
+                    self.color_a,
+                    self.color_b,
+                    fractal_noise(
+                        u * self.noise_scale,
+                        v * self.noise_scale,
+                        self.noise_octaves,
+                        self.seed,
+                    ),
+                ),
+            };
+
+            image::Rgba(color.map(|channel| (channel.clamp(0., 1.) * 255.).round() as u8))
+        })
+    }
+}
+
+fn mix(a: [f32; 4], b: [f32; 4], t: f32) -> [f32; 4] {
+    std::array::from_fn(|i| a[i] + (b[i] - a[i]) * t)
+}
+
+// Integer hash used as a tiny seeded random source for the noise lattice
+fn hash(x: u32, y: u32, seed: u32) -> u32 {
+    let mut h = x
+        .wrapping_mul(0x27d4_eb2d)
+        .wrapping_add(y.wrapping_mul(0x1656_67b1))
+        .wrapping_add(seed.wrapping_mul(0x9e37_79b9));
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x85eb_ca6b);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0xc2b2_ae35);
+    h ^ (h >> 16)
+}
+
+fn lattice_value(x: i32, y: i32, seed: u32) -> f32 {
+    hash(x as u32, y as u32, seed) as f32 / u32::MAX as f32
+}
+
+fn value_noise(x: f32, y: f32, seed: u32) -> f32 {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+    let (sx, sy) = (fx * fx * (3. - 2. * fx), fy * fy * (3. - 2. * fy));
+    let (x0, y0) = (x0 as i32, y0 as i32);
+
+    let top = lattice_value(x0, y0, seed)
+        + (lattice_value(x0 + 1, y0, seed) - lattice_value(x0, y0, seed)) * sx;
+    let bottom = lattice_value(x0, y0 + 1, seed)
+        + (lattice_value(x0 + 1, y0 + 1, seed) - lattice_value(x0, y0 + 1, seed)) * sx;
+
+    top + (bottom - top) * sy
+}
+
+fn fractal_noise(x: f32, y: f32, octaves: u32, seed: u32) -> f32 {
+    let mut value = 0.;
+    let mut amplitude = 0.5;
+    let mut frequency = 1.;
+    let mut total_amplitude = 0.;
+
+    for octave in 0..octaves {
+        value += value_noise(x * frequency, y * frequency, seed.wrapping_add(octave)) * amplitude;
+        total_amplitude += amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.;
+    }
+
+    value / total_amplitude
+}
@@ -0,0 +1,402 @@
+use std::sync::Arc;
+
+use eframe::egui::{
+    self, Color32, FontId, Galley, TextFormat, TextStyle,
+    cache::{ComputerMut, FrameCache},
+    text::LayoutJob,
+};
+
+use crate::material;
+
+static WGSL_KEYWORDS: &[&str] = &[
+    "alias",
+    "break",
+    "case",
+    "const",
+    "const_assert",
+    "continue",
+    "continuing",
+    "default",
+    "discard",
+    "else",
+    "enable",
+    "false",
+    "fn",
+    "for",
+    "if",
+    "let",
+    "loop",
+    "override",
+    "requires",
+    "return",
+    "struct",
+    "switch",
+    "true",
+    "var",
+    "while",
+];
+
+static WGSL_TYPES: &[&str] = &[
+    "array",
+    "atomic",
+    "bool",
+    "f16",
+    "f32",
+    "i32",
+    "ptr",
+    "sampler",
+    "sampler_comparison",
+    "u32",
+];
+
+/// WGSL builtin functions with their signatures, highlighted in snippets and offered as completions.
+pub static WGSL_BUILTINS: &[(&str, &str)] = &[
+    ("abs", "abs(e: T) -> T"),
+    ("acos", "acos(e: T) -> T"),
+    ("all", "all(e: vecN<bool>) -> bool"),
+    ("any", "any(e: vecN<bool>) -> bool"),
+    ("asin", "asin(e: T) -> T"),
+    ("atan", "atan(e: T) -> T"),
+    ("atan2", "atan2(y: T, x: T) -> T"),
+    ("ceil", "ceil(e: T) -> T"),
+    ("clamp", "clamp(e: T, low: T, high: T) -> T"),
+    ("cos", "cos(e: T) -> T"),
+    ("cosh", "cosh(e: T) -> T"),
+    ("cross", "cross(a: vec3<T>, b: vec3<T>) -> vec3<T>"),
+    ("degrees", "degrees(e: T) -> T"),
+    ("determinant", "determinant(e: matNxN<T>) -> T"),
+    ("distance", "distance(a: T, b: T) -> f32"),
+    ("dot", "dot(a: vecN<T>, b: vecN<T>) -> T"),
+    ("dpdx", "dpdx(e: T) -> T"),
+    ("dpdy", "dpdy(e: T) -> T"),
+    ("exp", "exp(e: T) -> T"),
+    ("exp2", "exp2(e: T) -> T"),
+    ("faceForward", "faceForward(e1: T, e2: T, e3: T) -> T"),
+    ("floor", "floor(e: T) -> T"),
+    ("fma", "fma(a: T, b: T, c: T) -> T"),
+    ("fract", "fract(e: T) -> T"),
+    ("fwidth", "fwidth(e: T) -> T"),
+    ("inverseSqrt", "inverseSqrt(e: T) -> T"),
+    ("length", "length(e: T) -> f32"),
+    ("log", "log(e: T) -> T"),
+    ("log2", "log2(e: T) -> T"),
+    ("max", "max(a: T, b: T) -> T"),
+    ("min", "min(a: T, b: T) -> T"),
+    ("mix", "mix(a: T, b: T, t: T) -> T"),
+    ("modf", "modf(e: T) -> __modf_result"),
+    ("normalize", "normalize(e: vecN<T>) -> vecN<T>"),
+    ("pow", "pow(e1: T, e2: T) -> T"),
+    ("radians", "radians(e: T) -> T"),
+    ("reflect", "reflect(e1: T, e2: T) -> T"),
+    (
+        "refract",
+        "refract(e1: vecN<T>, e2: vecN<T>, e3: T) -> vecN<T>",
+    ),
+    ("round", "round(e: T) -> T"),
+    ("saturate", "saturate(e: T) -> T"),
+    ("select", "select(f: T, t: T, cond: bool) -> T"),
+    ("sign", "sign(e: T) -> T"),
+    ("sin", "sin(e: T) -> T"),
+    ("sinh", "sinh(e: T) -> T"),
+    ("smoothstep", "smoothstep(low: T, high: T, x: T) -> T"),
+    ("sqrt", "sqrt(e: T) -> T"),
+    ("step", "step(edge: T, x: T) -> T"),
+    ("tan", "tan(e: T) -> T"),
+    ("tanh", "tanh(e: T) -> T"),
+    (
+        "textureDimensions",
+        "textureDimensions(t: texture_2d<f32>) -> vec2<u32>",
+    ),
+    (
+        "textureLoad",
+        "textureLoad(t: texture_2d<f32>, coords: vec2<i32>, level: i32) -> vec4<f32>",
+    ),
+    (
+        "textureSample",
+        "textureSample(t: texture_2d<f32>, s: sampler, coords: vec2<f32>) -> vec4<f32>",
+    ),
+    (
+        "textureSampleBias",
+        "textureSampleBias(t: texture_2d<f32>, s: sampler, coords: vec2<f32>, bias: f32) -> vec4<f32>",
+    ),
+    (
+        "textureSampleGrad",
+        "textureSampleGrad(t: texture_2d<f32>, s: sampler, coords: vec2<f32>, ddx: vec2<f32>, ddy: vec2<f32>) -> vec4<f32>",
+    ),
+    (
+        "textureSampleLevel",
+        "textureSampleLevel(t: texture_2d<f32>, s: sampler, coords: vec2<f32>, level: f32) -> vec4<f32>",
+    ),
+    ("transpose", "transpose(e: matRxC<T>) -> matCxR<T>"),
+    ("trunc", "trunc(e: T) -> T"),
+];
+
+// The engine passes uniforms to snippets through this struct
+static UNIFORMS_STRUCT: &str = "scene_instance";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Language {
+    Wgsl,
+    Toml,
+}
+
+/// Names declared by the material that snippets can refer to.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Symbols {
+    // Uniform names with their declared types
+    pub uniforms: Vec<(String, String)>,
+    // Texture bindings along with their `sampler_<tex>` samplers
+    pub textures: Vec<String>,
+}
+
+impl Symbols {
+    pub fn new(uniforms_text: &str, textures_text: &str) -> Self {
+        let uniforms = uniforms_text
+            .parse::<toml::Table>()
+            .map(|uniforms| {
+                uniforms
+                    .into_iter()
+                    .map(|(name, value)| {
+                        let uniform_type = match &value {
+                            toml::Value::String(uniform_type) => Some(uniform_type.as_str()),
+                            toml::Value::Table(table) => table
+                                .get("type")
+                                .and_then(|uniform_type| uniform_type.as_str()),
+                            _ => None,
+                        };
+                        (name, uniform_type.unwrap_or_default().to_string())
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let textures = material::parse_texture_bindings(textures_text)
+            .into_iter()
+            .flat_map(|binding| [format!("sampler_{}", binding.name), binding.name])
+            .collect();
+
+        Self { uniforms, textures }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TokenKind {
+    Plain,
+    Keyword,
+    Type,
+    Builtin,
+    Number,
+    Comment,
+    String,
+    Key,
+    Header,
+    Uniform,
+    Texture,
+}
+
+impl TokenKind {
+    fn color(self, dark_mode: bool, text_color: Color32) -> Color32 {
+        let (dark, light) = match self {
+            TokenKind::Plain => return text_color,
+            TokenKind::Keyword => ((197, 134, 192), (175, 0, 219)),
+            TokenKind::Type | TokenKind::Header => ((78, 201, 176), (38, 127, 153)),
+            TokenKind::Builtin => ((220, 220, 170), (121, 94, 38)),
+            TokenKind::Number => ((181, 206, 168), (9, 134, 88)),
+            TokenKind::Comment => ((106, 153, 85), (0, 128, 0)),
+            TokenKind::String => ((206, 145, 120), (163, 21, 21)),
+            TokenKind::Key => ((156, 220, 254), (0, 16, 128)),
+            TokenKind::Uniform => ((79, 193, 255), (0, 112, 193)),
+            TokenKind::Texture => ((255, 160, 122), (190, 80, 40)),
+        };
+        let (r, g, b) = if dark_mode { dark } else { light };
+        Color32::from_rgb(r, g, b)
+    }
+}
+
+fn is_wgsl_type(word: &str) -> bool {
+    WGSL_TYPES.contains(&word)
+        || word.starts_with("texture_")
+        || material::is_valid_uniform_type(word)
+        || matches!(word, "vec2" | "vec3" | "vec4")
+        || (word.len() == 6 && word.starts_with("mat") && word.as_bytes()[4] == b'x')
+}
+
+// Whether the word starting at `start` follows `scene_instance.`
+fn is_uniform_member(text: &str, start: usize) -> bool {
+    let Some(before_dot) = text[..start].trim_end().strip_suffix('.') else {
+        return false;
+    };
+    before_dot
+        .trim_end()
+        .strip_suffix(UNIFORMS_STRUCT)
+        .is_some_and(|rest| !rest.ends_with(|c: char| c.is_alphanumeric() || c == '_'))
+}
+
+// Splits `text` into highlighted spans, as byte ranges
+fn tokenize(text: &str, language: Language, symbols: &Symbols) -> Vec<(usize, usize, TokenKind)> {
+    let bytes = text.as_bytes();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+    let mut line_start = true;
+
+    while index < bytes.len() {
+        let start = index;
+        let c = bytes[index];
+
+        let kind = if language == Language::Wgsl && text[index..].starts_with("//")
+            || language == Language::Toml && c == b'#'
+        {
+            index = text[index..]
+                .find('\n')
+                .map_or(bytes.len(), |end| index + end);
+            TokenKind::Comment
+        } else if language == Language::Wgsl && text[index..].starts_with("/*") {
+            index = text[index + 2..]
+                .find("*/")
+                .map_or(bytes.len(), |end| index + 2 + end + 2);
+            TokenKind::Comment
+        } else if language == Language::Toml && c == b'"' {
+            index += 1;
+            while index < bytes.len() && bytes[index] != b'"' && bytes[index] != b'\n' {
+                index += 1;
+                // Skip the escaped character, which may be more than one byte
+                if bytes[index - 1] == b'\\' {
+                    index += text[index..].chars().next().map_or(0, char::len_utf8);
+                }
+            }
+            index = (index + 1).min(bytes.len());
+            TokenKind::String
+        } else if language == Language::Toml
+            && c == b'['
+            && line_start
+            && bytes
+                .get(index + 1)
+                .is_some_and(|next| next.is_ascii_alphabetic() || *next == b'[')
+        {
+            index = text[index..]
+                .find('\n')
+                .map_or(bytes.len(), |end| index + end);
+            TokenKind::Header
+        } else if c.is_ascii_digit()
+            || c == b'.' && bytes.get(index + 1).is_some_and(u8::is_ascii_digit)
+        {
+            while index < bytes.len() {
+                let c = bytes[index];
+                let exponent_sign = (c == b'+' || c == b'-')
+                    && matches!(bytes[index - 1], b'e' | b'E')
+                    && !text[start..index].starts_with("0x");
+                if !(c.is_ascii_alphanumeric() || c == b'.' || c == b'_' || exponent_sign) {
+                    break;
+                }
+                index += 1;
+            }
+            TokenKind::Number
+        } else if c.is_ascii_alphabetic() || c == b'_' {
+            while index < bytes.len()
+                && (bytes[index].is_ascii_alphanumeric() || bytes[index] == b'_')
+            {
+                index += 1;
+            }
+            let word = &text[start..index];
+
+            match language {
+                Language::Wgsl => {
+                    if word == UNIFORMS_STRUCT
+                        || is_uniform_member(text, start)
+                            && symbols.uniforms.iter().any(|(name, _)| name == word)
+                    {
+                        TokenKind::Uniform
+                    } else if symbols.textures.iter().any(|name| name == word) {
+                        TokenKind::Texture
+                    } else if WGSL_KEYWORDS.contains(&word) {
+                        TokenKind::Keyword
+                    } else if is_wgsl_type(word) {
+                        TokenKind::Type
+                    } else if WGSL_BUILTINS.iter().any(|(name, _)| *name == word) {
+                        TokenKind::Builtin
+                    } else {
+                        TokenKind::Plain
+                    }
+                }
+                Language::Toml => {
+                    let is_key = text[index..]
+                        .trim_start_matches([' ', '\t'])
+                        .starts_with('=');
+                    if is_key && line_start && symbols.uniforms.iter().any(|(name, _)| name == word)
+                    {
+                        TokenKind::Uniform
+                    } else if is_key
+                        && line_start
+                        && symbols.textures.iter().any(|name| name == word)
+                    {
+                        TokenKind::Texture
+                    } else if is_key {
+                        TokenKind::Key
+                    } else if word == "true" || word == "false" {
+                        TokenKind::Keyword
+                    } else {
+                        TokenKind::Plain
+                    }
+                }
+            }
+        } else {
+            // Whole utf-8 characters, so spans always land on char boundaries
+            index += text[index..].chars().next().map_or(1, char::len_utf8);
+            TokenKind::Plain
+        };
+
+        if c == b'\n' {
+            line_start = true;
+        } else if !c.is_ascii_whitespace() {
+            line_start = false;
+        }
+        tokens.push((start, index, kind));
+    }
+
+    tokens
+}
+
+#[derive(Default)]
+struct Highlighter;
+
+type HighlightKey<'a> = (&'a FontId, bool, Color32, Language, &'a str, &'a Symbols);
+
+impl ComputerMut<HighlightKey<'_>, LayoutJob> for Highlighter {
+    fn compute(
+        &mut self,
+        (font_id, dark_mode, text_color, language, text, symbols): HighlightKey<'_>,
+    ) -> LayoutJob {
+        let mut layout_job = LayoutJob::default();
+        for (start, end, kind) in tokenize(text, language, symbols) {
+            layout_job.append(
+                &text[start..end],
+                0.,
+                TextFormat::simple(font_id.clone(), kind.color(dark_mode, text_color)),
+            );
+        }
+        layout_job
+    }
+}
+
+type HighlightCache = FrameCache<LayoutJob, Highlighter>;
+
+/// Highlighted layout for a code box, use from a `TextEdit::layouter`.
+pub fn layout(
+    ui: &egui::Ui,
+    text: &str,
+    wrap_width: f32,
+    language: Language,
+    symbols: &Symbols,
+) -> Arc<Galley> {
+    let font_id = TextStyle::Monospace.resolve(ui.style());
+    let (dark_mode, text_color) = (ui.visuals().dark_mode, ui.visuals().text_color());
+
+    let mut layout_job = ui.ctx().memory_mut(|memory| {
+        memory
+            .caches
+            .cache::<HighlightCache>()
+            .get((&font_id, dark_mode, text_color, language, text, symbols))
+    });
+    layout_job.wrap.max_width = wrap_width;
+
+    ui.fonts(|fonts| fonts.layout_job(layout_job))
+}
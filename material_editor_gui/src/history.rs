@@ -0,0 +1,381 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use eframe::egui::{self, Color32, RichText, ScrollArea};
+use serde::{Deserialize, Serialize};
+
+use crate::material::MaterialSections;
+
+static HISTORY_DIR: &str = "./temp/history/";
+static MAX_HISTORY_ENTRIES: usize = 200;
+
+/// A successfully compiled version of a material, saved to the temp directory.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HistoryEntry {
+    // Milliseconds since the unix epoch
+    pub timestamp: u64,
+    pub material_name: String,
+    pub sections: MaterialSections,
+    // Default value of every uniform at compile time, as written in [uniform_types]
+    pub uniform_values: BTreeMap<String, String>,
+}
+
+impl HistoryEntry {
+    pub fn new(material_name: String, sections: MaterialSections) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_millis() as u64)
+            .unwrap_or_default();
+
+        Self {
+            timestamp,
+            material_name,
+            uniform_values: uniform_values(&sections.uniforms),
+            sections,
+        }
+    }
+
+    fn file_path(&self) -> PathBuf {
+        PathBuf::from(HISTORY_DIR).join(format!("{}_{}.json", self.timestamp, self.material_name))
+    }
+}
+
+fn uniform_values(uniforms_text: &str) -> BTreeMap<String, String> {
+    uniforms_text
+        .parse::<toml::Table>()
+        .map(|uniforms| {
+            uniforms
+                .into_iter()
+                .filter_map(|(name, value)| {
+                    let default = value.as_table()?.get("default")?;
+                    Some((name, default.to_string()))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// ex: `2026-10-18 14:03:12 UTC`
+pub fn format_timestamp(timestamp: u64) -> String {
+    let seconds = timestamp / 1000;
+    let (days, seconds_of_day) = (seconds / 86_400, seconds % 86_400);
+
+    // Days since the epoch to a civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02} UTC",
+        seconds_of_day / 3600,
+        seconds_of_day % 3600 / 60,
+        seconds_of_day % 60
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DiffLine<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+// Line diff from the longest common subsequence of both texts
+fn diff_lines<'a>(old: &'a str, new: &'a str) -> Vec<DiffLine<'a>> {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+
+    let mut common = vec![vec![0usize; new_lines.len() + 1]; old_lines.len() + 1];
+    for i in (0..old_lines.len()).rev() {
+        for j in (0..new_lines.len()).rev() {
+            common[i][j] = if old_lines[i] == new_lines[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old_lines.len() && j < new_lines.len() {
+        if old_lines[i] == new_lines[j] {
+            diff.push(DiffLine::Same(old_lines[i]));
+            i += 1;
+            j += 1;
+        } else if common[i + 1][j] >= common[i][j + 1] {
+            diff.push(DiffLine::Removed(old_lines[i]));
+            i += 1;
+        } else {
+            diff.push(DiffLine::Added(new_lines[j]));
+            j += 1;
+        }
+    }
+    diff.extend(old_lines[i..].iter().map(|line| DiffLine::Removed(line)));
+    diff.extend(new_lines[j..].iter().map(|line| DiffLine::Added(line)));
+
+    diff
+}
+
+pub enum HistoryAction {
+    None,
+    Restore(MaterialSections),
+}
+
+/// Every successfully compiled material, newest first, persisted in the temp directory.
+#[derive(Default)]
+pub struct CompileHistory {
+    entries: Vec<HistoryEntry>,
+    // Timestamp of the entry shown in the diff window
+    diff_entry: Option<u64>,
+}
+
+impl CompileHistory {
+    pub fn load() -> Self {
+        let mut entries: Vec<HistoryEntry> = fs::read_dir(HISTORY_DIR)
+            .map(|dir_entries| {
+                dir_entries
+                    .filter_map(|dir_entry| fs::read_to_string(dir_entry.ok()?.path()).ok())
+                    .filter_map(|json| serde_json::from_str(&json).ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        entries.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+
+        Self {
+            entries,
+            diff_entry: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn record(&mut self, entry: HistoryEntry) {
+        // Recompiling the same sources doesn't make a new version
+        if self
+            .entries
+            .first()
+            .is_some_and(|latest| latest.sections == entry.sections)
+        {
+            return;
+        }
+
+        let _ = fs::create_dir_all(HISTORY_DIR);
+        match serde_json::to_string_pretty(&entry) {
+            Ok(json) => {
+                if let Err(error) = fs::write(entry.file_path(), json) {
+                    println!("Failed to write compile history with error {error}");
+                }
+            }
+            Err(error) => println!("Failed to serialize compile history with error {error}"),
+        }
+
+        self.entries.insert(0, entry);
+        for removed_entry in self
+            .entries
+            .drain(MAX_HISTORY_ENTRIES.min(self.entries.len())..)
+        {
+            let _ = fs::remove_file(removed_entry.file_path());
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui) -> HistoryAction {
+        let mut action = HistoryAction::None;
+
+        if self.entries.is_empty() {
+            ui.label("Nothing compiled yet");
+        }
+
+        ScrollArea::vertical()
+            .id_salt("compile_history_scroll")
+            .max_height(160.)
+            .show(ui, |ui| {
+                for entry in &self.entries {
+                    ui.horizontal(|ui| {
+                        ui.label(format_timestamp(entry.timestamp));
+                        ui.label(&entry.material_name).on_hover_ui(|ui| {
+                            for (name, value) in &entry.uniform_values {
+                                ui.monospace(format!("{name} = {value}"));
+                            }
+                        });
+                        if ui.button("Diff").clicked() {
+                            self.diff_entry = Some(entry.timestamp);
+                        }
+                        if ui.button("Restore").clicked() {
+                            action = HistoryAction::Restore(entry.sections.clone());
+                        }
+                    });
+                }
+            });
+
+        action
+    }
+
+    /// Window diffing the selected entry against the current sections.
+    pub fn show_diff(&mut self, ctx: &egui::Context, current: &MaterialSections) -> HistoryAction {
+        let mut action = HistoryAction::None;
+        let Some(entry) = self.diff_entry.and_then(|timestamp| {
+            self.entries
+                .iter()
+                .find(|entry| entry.timestamp == timestamp)
+        }) else {
+            return action;
+        };
+
+        let mut open = true;
+        egui::Window::new(format!(
+            "Diff {} - {}",
+            entry.material_name,
+            format_timestamp(entry.timestamp)
+        ))
+        .id(egui::Id::new("compile_history_diff"))
+        .open(&mut open)
+        .default_size([480., 480.])
+        .show(ctx, |ui| {
+            ui.label("- compiled version, + current text");
+            if ui.button("Restore and compile").clicked() {
+                action = HistoryAction::Restore(entry.sections.clone());
+            }
+
+            ScrollArea::vertical().show(ui, |ui| {
+                for (section, old, new) in [
+                    ("Uniforms", &entry.sections.uniforms, &current.uniforms),
+                    ("Textures", &entry.sections.textures, &current.textures),
+                    (
+                        "World Offset",
+                        &entry.sections.world_offset,
+                        &current.world_offset,
+                    ),
+                    (
+                        "Fragment Color",
+                        &entry.sections.frag_color,
+                        &current.frag_color,
+                    ),
+                ] {
+                    ui.strong(section);
+                    let diff = diff_lines(old, new);
+                    if diff.iter().all(|line| matches!(line, DiffLine::Same(_))) {
+                        ui.label("No changes");
+                        continue;
+                    }
+
+                    for line in diff {
+                        let text = match line {
+                            DiffLine::Same(line) => RichText::new(format!("  {line}")),
+                            DiffLine::Removed(line) => {
+                                RichText::new(format!("- {line}")).color(Color32::LIGHT_RED)
+                            }
+                            DiffLine::Added(line) => {
+                                RichText::new(format!("+ {line}")).color(Color32::LIGHT_GREEN)
+                            }
+                        };
+                        ui.label(text.monospace());
+                    }
+                }
+            });
+        });
+
+        if !open {
+            self.diff_entry = None;
+        }
+
+        action
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_texts_have_no_changes() {
+        assert_eq!(
+            diff_lines("a\nb", "a\nb"),
+            [DiffLine::Same("a"), DiffLine::Same("b")]
+        );
+        assert!(diff_lines("", "").is_empty());
+    }
+
+    #[test]
+    fn inserted_lines_are_added() {
+        assert_eq!(
+            diff_lines("a\nc", "a\nb\nc\nd"),
+            [
+                DiffLine::Same("a"),
+                DiffLine::Added("b"),
+                DiffLine::Same("c"),
+                DiffLine::Added("d"),
+            ]
+        );
+        assert_eq!(diff_lines("", "a"), [DiffLine::Added("a")]);
+    }
+
+    #[test]
+    fn deleted_lines_are_removed() {
+        assert_eq!(
+            diff_lines("a\nb\nc", "b"),
+            [
+                DiffLine::Removed("a"),
+                DiffLine::Same("b"),
+                DiffLine::Removed("c"),
+            ]
+        );
+        assert_eq!(diff_lines("a", ""), [DiffLine::Removed("a")]);
+    }
+
+    #[test]
+    fn changed_lines_are_removed_then_added() {
+        assert_eq!(
+            diff_lines("let a = 1.;\nreturn a;", "let a = 2.;\nreturn a;\n// done"),
+            [
+                DiffLine::Removed("let a = 1.;"),
+                DiffLine::Added("let a = 2.;"),
+                DiffLine::Same("return a;"),
+                DiffLine::Added("// done"),
+            ]
+        );
+    }
+
+    #[test]
+    fn saved_entries_restore_the_compiled_sections() {
+        let sections = MaterialSections {
+            uniforms: "tint = { type = \"vec4f\", default = [1.0, 0.5, 0.5, 1.0] }".to_string(),
+            textures: "color_tex = \"linear\"".to_string(),
+            world_offset: "return vec2f(0., 0.);".to_string(),
+            frag_color: "return scene_instance.tint;".to_string(),
+            ..Default::default()
+        };
+        let entry = HistoryEntry::new("default_sprite".to_string(), sections.clone());
+
+        let json = serde_json::to_string(&entry).unwrap();
+        let restored: HistoryEntry = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.sections, sections);
+        assert_eq!(restored.uniform_values["tint"], "[1.0, 0.5, 0.5, 1.0]");
+    }
+
+    #[test]
+    fn timestamps_format_as_utc_dates() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(
+            format_timestamp(1_792_332_192_000),
+            "2026-10-18 14:03:12 UTC"
+        );
+    }
+}
@@ -0,0 +1,334 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use eframe::egui::{self, Align, Layout};
+use serde::{Deserialize, Serialize};
+
+// Sizes closer than this to the saved ones are not worth rewriting the settings for
+pub static RESIZE_TOLERANCE: f32 = 0.5;
+
+/// The parts of the editor window that can be moved between dock areas.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Panel {
+    Uniforms,
+    Textures,
+    Snippets,
+    Parameters,
+    Diagnostics,
+}
+
+impl Panel {
+    pub const ALL: [Panel; 5] = [
+        Panel::Uniforms,
+        Panel::Textures,
+        Panel::Snippets,
+        Panel::Parameters,
+        Panel::Diagnostics,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Panel::Uniforms => "Uniforms",
+            Panel::Textures => "Textures",
+            Panel::Snippets => "Snippets",
+            Panel::Parameters => "Parameters",
+            Panel::Diagnostics => "Diagnostics",
+        }
+    }
+
+    // Stacked top to bottom in the order above, like the editor before panels could be moved
+    fn default_dock(self) -> DockArea {
+        match self {
+            Panel::Diagnostics => DockArea::Bottom,
+            _ => DockArea::Center,
+        }
+    }
+
+    // Height when stacked in a side or center dock, width when in the bottom dock
+    fn default_size(self) -> f32 {
+        match self {
+            Panel::Uniforms => 150.,
+            Panel::Textures => 220.,
+            Panel::Snippets => 320.,
+            Panel::Parameters => 120.,
+            Panel::Diagnostics => 200.,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DockArea {
+    Left,
+    Center,
+    Right,
+    Bottom,
+    Hidden,
+}
+
+impl DockArea {
+    pub const ALL: [DockArea; 5] = [
+        DockArea::Left,
+        DockArea::Center,
+        DockArea::Right,
+        DockArea::Bottom,
+        DockArea::Hidden,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DockArea::Left => "Left",
+            DockArea::Center => "Center",
+            DockArea::Right => "Right",
+            DockArea::Bottom => "Bottom",
+            DockArea::Hidden => "Hidden",
+        }
+    }
+
+    // Width of the side docks, height of the bottom dock. The center takes what is left
+    fn default_size(self) -> f32 {
+        match self {
+            DockArea::Left | DockArea::Right => 320.,
+            DockArea::Bottom => 180.,
+            DockArea::Center | DockArea::Hidden => 0.,
+        }
+    }
+}
+
+pub enum LayoutChange {
+    Move(Panel, DockArea),
+    ResizePanel(Panel, f32),
+    ResizeDock(DockArea, f32),
+}
+
+/// Where the editor panels are docked and how large they are, kept in the user settings.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct PanelLayout {
+    // Collapsing headers left open, by id salt
+    pub open_sections: BTreeSet<String>,
+    // Panels sharing a dock area are stacked in this order. Panels missing here use their default dock
+    pub docked_panels: Vec<(Panel, DockArea)>,
+    pub panel_sizes: BTreeMap<Panel, f32>,
+    pub dock_sizes: BTreeMap<DockArea, f32>,
+    // Height of the world offset editor, the fragment color editor below it takes the rest
+    pub world_offset_height: f32,
+    // Bumped by reset, egui keeps panel sizes by id and would otherwise ignore the default sizes
+    #[serde(skip)]
+    generation: u32,
+}
+
+impl Default for PanelLayout {
+    fn default() -> Self {
+        Self {
+            open_sections: BTreeSet::new(),
+            docked_panels: Panel::ALL
+                .into_iter()
+                .map(|panel| (panel, panel.default_dock()))
+                .collect(),
+            panel_sizes: BTreeMap::new(),
+            dock_sizes: BTreeMap::new(),
+            world_offset_height: 120.,
+            generation: 0,
+        }
+    }
+}
+
+impl PanelLayout {
+    pub fn is_open(&self, id_salt: &str) -> bool {
+        self.open_sections.contains(id_salt)
+    }
+
+    pub fn toggle(&mut self, id_salt: &str) {
+        if !self.open_sections.remove(id_salt) {
+            self.open_sections.insert(id_salt.to_string());
+        }
+    }
+
+    pub fn dock(&self, panel: Panel) -> DockArea {
+        self.docked_panels
+            .iter()
+            .find(|(docked_panel, _)| *docked_panel == panel)
+            .map(|(_, dock)| *dock)
+            .unwrap_or(panel.default_dock())
+    }
+
+    pub fn panels_in(&self, dock: DockArea) -> Vec<Panel> {
+        let mut panels: Vec<Panel> = self
+            .docked_panels
+            .iter()
+            .filter(|(_, panel_dock)| *panel_dock == dock)
+            .map(|(panel, _)| *panel)
+            .collect();
+        for panel in Panel::ALL {
+            if !panels.contains(&panel) && self.dock(panel) == dock {
+                panels.push(panel);
+            }
+        }
+        panels
+    }
+
+    pub fn panel_size(&self, panel: Panel) -> f32 {
+        self.panel_sizes
+            .get(&panel)
+            .copied()
+            .unwrap_or(panel.default_size())
+    }
+
+    pub fn dock_size(&self, dock: DockArea) -> f32 {
+        self.dock_sizes
+            .get(&dock)
+            .copied()
+            .unwrap_or(dock.default_size())
+    }
+
+    /// Applies a change made while showing the docks, open sections are kept.
+    pub fn apply(&mut self, change: LayoutChange) {
+        match change {
+            // Moved panels go to the end of their new dock
+            LayoutChange::Move(panel, dock) => {
+                self.docked_panels
+                    .retain(|(docked_panel, _)| *docked_panel != panel);
+                self.docked_panels.push((panel, dock));
+                self.panel_sizes.remove(&panel);
+            }
+            LayoutChange::ResizePanel(panel, size) => {
+                self.panel_sizes.insert(panel, size);
+            }
+            LayoutChange::ResizeDock(dock, size) => {
+                self.dock_sizes.insert(dock, size);
+            }
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self {
+            open_sections: std::mem::take(&mut self.open_sections),
+            generation: self.generation + 1,
+            ..Self::default()
+        };
+    }
+
+    /// Id for a resizable panel, changes when the layout is reset.
+    pub fn panel_id(&self, id_salt: impl std::hash::Hash) -> egui::Id {
+        egui::Id::new((id_salt, self.generation))
+    }
+}
+
+pub enum PanelPart {
+    // Shown in the panel's header row, before its dock menu
+    Title,
+    Contents,
+}
+
+/// Shows the panels docked in `dock` stacked in `ui`, each but the last one resizable.
+///
+/// `add_panel` is called twice per panel, for its title and for its contents.
+pub fn show_dock(
+    ui: &mut egui::Ui,
+    layout: &PanelLayout,
+    dock: DockArea,
+    changes: &mut Vec<LayoutChange>,
+    mut add_panel: impl FnMut(&mut egui::Ui, Panel, PanelPart),
+) {
+    let panels = layout.panels_in(dock);
+    if panels.is_empty() {
+        egui::CentralPanel::default().show_inside(ui, |ui| {
+            ui.centered_and_justified(|ui| {
+                ui.weak("No panels docked here, see Layout");
+            });
+        });
+        return;
+    }
+
+    for (index, panel) in panels.iter().copied().enumerate() {
+        // Egui keeps a panel's size by id, so a panel moved to another dock starts from its default size
+        let id = layout.panel_id(("docked_panel", panel, dock));
+        let saved_size = layout.panel_size(panel);
+
+        if index + 1 == panels.len() {
+            egui::CentralPanel::default().show_inside(ui, |ui| {
+                show_panel(ui, panel, dock, changes, &mut add_panel);
+            });
+        } else if dock == DockArea::Bottom {
+            let response = egui::SidePanel::left(id)
+                .resizable(true)
+                .default_width(saved_size)
+                .show_inside(ui, |ui| {
+                    show_panel(ui, panel, dock, changes, &mut add_panel);
+                });
+            let size = response.response.rect.width();
+            if (size - saved_size).abs() > RESIZE_TOLERANCE {
+                changes.push(LayoutChange::ResizePanel(panel, size));
+            }
+        } else {
+            let response = egui::TopBottomPanel::top(id)
+                .resizable(true)
+                .default_height(saved_size)
+                .show_inside(ui, |ui| {
+                    show_panel(ui, panel, dock, changes, &mut add_panel);
+                });
+            let size = response.response.rect.height();
+            if (size - saved_size).abs() > RESIZE_TOLERANCE {
+                changes.push(LayoutChange::ResizePanel(panel, size));
+            }
+        }
+    }
+}
+
+fn show_panel(
+    ui: &mut egui::Ui,
+    panel: Panel,
+    dock: DockArea,
+    changes: &mut Vec<LayoutChange>,
+    add_panel: &mut impl FnMut(&mut egui::Ui, Panel, PanelPart),
+) {
+    ui.horizontal(|ui| {
+        add_panel(ui, panel, PanelPart::Title);
+        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+            ui.menu_button("Dock", |ui| {
+                for dock_area in DockArea::ALL {
+                    if ui
+                        .selectable_label(dock_area == dock, dock_area.name())
+                        .clicked()
+                    {
+                        if dock_area != dock {
+                            changes.push(LayoutChange::Move(panel, dock_area));
+                        }
+                        ui.close_menu();
+                    }
+                }
+            });
+        });
+    });
+    ui.separator();
+    add_panel(ui, panel, PanelPart::Contents);
+}
+
+/// Lists every panel with the dock it is in, to bring back hidden panels or reset the layout.
+pub fn layout_menu(
+    ui: &mut egui::Ui,
+    layout: &PanelLayout,
+    changes: &mut Vec<LayoutChange>,
+) -> bool {
+    for panel in Panel::ALL {
+        let panel_dock = layout.dock(panel);
+        ui.menu_button(format!("{}: {}", panel.name(), panel_dock.name()), |ui| {
+            for dock_area in DockArea::ALL {
+                if ui
+                    .radio(dock_area == panel_dock, dock_area.name())
+                    .clicked()
+                {
+                    if dock_area != panel_dock {
+                        changes.push(LayoutChange::Move(panel, dock_area));
+                    }
+                    ui.close_menu();
+                }
+            }
+        });
+    }
+    ui.separator();
+    let reset = ui.button("Reset Layout").clicked();
+    if reset {
+        ui.close_menu();
+    }
+    reset
+}
@@ -0,0 +1,5 @@
+//! Material model shared by the editor and the `validate_materials` command.
+
+pub mod blend;
+pub mod material;
+pub mod shader;
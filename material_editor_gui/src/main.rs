@@ -3,7 +3,8 @@
 #![allow(rustdoc::missing_crate_level_docs)]
 use core::f32;
 use std::{
-    env,
+    cmp::Ordering as CmpOrdering,
+    collections::VecDeque,
     fs::{self, File, OpenOptions},
     io::Write,
     path::{Path, PathBuf},
@@ -11,19 +12,53 @@ use std::{
         Mutex,
         atomic::{AtomicBool, Ordering},
     },
+    time::{Duration, Instant, SystemTime},
 };
 
-use eframe::egui::{self, CentralPanel, ComboBox, ScrollArea, TextEdit};
+use eframe::egui::{self, CentralPanel, Grid, ScrollArea, TextEdit};
 use memmap2::MmapMut;
 use once_cell::sync::Lazy;
-use regex::Regex;
 use rfd::FileDialog;
-use serde::{Deserialize, Serialize};
-use serde_json;
 
-static MATERIAL_EDITOR_VERSION: u32 = 0;
+mod completion;
+mod document;
+mod find;
+mod generator;
+mod highlight;
+mod history;
+mod layout;
+mod render_target;
+mod settings;
+mod shortcuts;
+mod textures;
+mod undo;
+mod workspace;
+
+use completion::Autocomplete;
+use document::MaterialDocument;
+use find::{FindReplace, RenameSymbol, Section};
+use generator::TextureGenerator;
+use highlight::{Language, Symbols};
+use history::{CompileHistory, HistoryAction, HistoryEntry};
+use layout::{DockArea, LayoutChange, Panel, PanelPart};
+use material::{MaterialSections, SamplerDesc};
+use material_editor_gui::{blend, material};
+use render_target::{PipelineTarget, RenderTargetInfo};
+use settings::UserSettings;
+use shortcuts::{Action, CommandPalette, ShortcutEditor};
+use textures::{
+    ChannelViewer, EngineTexture, TextureBrowser, TextureBrowserAction, TextureInfoCache,
+};
+use undo::{EditorSnapshot, UndoAction};
+use workspace::{Workspace, WorkspaceAction};
+
+// Settings written by older versions are migrated, see settings.rs
+static MATERIAL_EDITOR_VERSION: u32 = 1;
 static USER_SETTINGS_PATH: &str = "./temp/user_settings.json";
 static MAX_TEXTURES: usize = 16;
+static MESSAGE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+static LIVE_COMPILE_DEBOUNCE: Duration = Duration::from_millis(600);
+static MATERIAL_FILE_POLL_INTERVAL: Duration = Duration::from_millis(500);
 
 static SHARED_MEM_FILE: Lazy<Mutex<MmapMut>> = Lazy::new(|| {
     let file = OpenOptions::new()
@@ -42,126 +77,1330 @@ fn get_config() -> &'static mut UserSettings {
     unsafe { GLOBAL_CONFIG.as_mut().unwrap() }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct UserSettings {
-    version: u32,
-    shader_directory: PathBuf,
-    texture_directories: [PathBuf; MAX_TEXTURES],
-}
-
 struct MaterialEditor {
-    shader_path: PathBuf,
+    // Open materials, one per tab. The active one is the material pushed to the engine
+    documents: Vec<MaterialDocument>,
+    active_document: usize,
+    next_document_id: u64,
+    // Preview sprites reported by the engine module, each tab can be shown on a different one
+    preview_entity_count: usize,
+
+    // Reported by the engine module, asset-relative texture paths resolve from it
+    asset_root: Option<PathBuf>,
+    // Picked texture waiting for the user to decide how to handle it lying outside the asset tree
+    outside_asset_texture: Option<(usize, String, PathBuf)>,
+
+    texture_infos: TextureInfoCache,
+    texture_browser: Option<TextureBrowser>,
+    channel_viewer: Option<ChannelViewer>,
+    texture_generator: TextureGenerator,
+    // Textures the engine module currently holds for the editor
+    engine_textures: Vec<EngineTexture>,
+    // Materials the engine module registered for the editor and hasn't removed yet
+    editor_material_count: Option<usize>,
+
+    // Live mode compiles automatically once edits settle, as long as the material is valid
+    live_mode: bool,
+
+    // Id, material name and sections of every compile sent and not answered yet, oldest first
+    in_flight_compiles: VecDeque<(u64, String, MaterialSections)>,
+    next_compile_id: u64,
+    compile_history: CompileHistory,
+
+    // Render targets the engine reports and the one compiled pipelines are built for, unknown until the first report
+    render_targets: Vec<RenderTargetInfo>,
+    pipeline_target: Option<PipelineTarget>,
+
+    // Whether the engine keeps showing the last good material when a compile fails
+    keep_last_good: bool,
+    compile_error: Option<String>,
+
+    // Latest notification from the engine, shown under the compile button
+    status_text: String,
+
+    // Commands waiting for their turn in shared memory, one is sent per exchange
+    pending_commands: VecDeque<String>,
+
+    // Completion popup shared by the snippet editors
+    autocomplete: Autocomplete,
 
-    textures: [String; MAX_TEXTURES],
+    // Materials in the workspace directory, listed in a side panel
+    workspace: Workspace,
+    // Panels were moved or resized, the settings are saved once the pointer is released
+    layout_changed: bool,
 
-    textures_text: String,
-    uniforms_text: String,
-    world_offset_text: String,
-    frag_color_text: String,
+    command_palette: CommandPalette,
+    shortcut_editor: ShortcutEditor,
+    find_replace: FindReplace,
+    rename_symbol: RenameSymbol,
+
+    // Tab or window close waiting on the user to save or discard unsaved edits
+    close_request: Option<CloseRequest>,
+    // Set once the user agreed to close the window despite unsaved edits
+    confirmed_close: bool,
+    window_title: String,
+}
+
+#[derive(Clone, Copy)]
+enum CloseRequest {
+    // Closing the tab of the document with this id
+    Document(u64),
+    Window,
 }
 
 impl MaterialEditor {
+    fn document(&self) -> &MaterialDocument {
+        &self.documents[self.active_document]
+    }
+
+    fn document_mut(&mut self) -> &mut MaterialDocument {
+        &mut self.documents[self.active_document]
+    }
+
+    // Switches to the document's tab if it is already open. Otherwise it replaces an untouched
+    // untitled tab or opens in a new one, shown on the same preview sprite as the current tab
+    fn open_material(&mut self, file_path: &PathBuf) {
+        get_config().add_recent_file(file_path);
+        if let Some(index) = self
+            .documents
+            .iter()
+            .position(|document| document.shader_path == *file_path)
+        {
+            self.activate_document(index);
+            return;
+        }
+
+        if !self.document().is_untitled() || self.document().has_unsaved_edits() {
+            let preview_entity = self.document().preview_entity;
+            self.documents
+                .push(MaterialDocument::new(self.next_document_id, preview_entity));
+            self.next_document_id += 1;
+            self.active_document = self.documents.len() - 1;
+        }
+
+        self.load_material(file_path);
+        self.activate_document(self.active_document);
+    }
+
     fn load_material(&mut self, file_path: &PathBuf) {
-        self.shader_path = file_path.clone();
-        get_config().shader_directory = self
+        let document = self.document_mut();
+        document.shader_path = file_path.clone();
+        get_config().shader_directory = document
             .shader_path
             .parent()
             .unwrap_or(PathBuf::from("./").as_path())
             .to_path_buf();
 
-        if let Ok(material_toml) = fs::read_to_string(&self.shader_path) {
-            let key = "[uniform_types]";
-            if let Some(snippet_key_idx) = material_toml.find(key) {
-                let snippet_start = snippet_key_idx + key.len();
-                let snippet = &material_toml[snippet_start..];
-                let snippet_end = [
-                    "[texture_descs]",
-                    "[get_world_offset]",
-                    "[get_fragment_color]",
-                ]
-                .iter()
-                .filter_map(|key| snippet.find(key))
-                .min()
-                .unwrap_or(snippet.len());
+        document.blend_error = None;
+        match fs::read_to_string(&document.shader_path) {
+            Ok(material_toml) => {
+                let (sections, blend_error) = MaterialSections::from_toml(&material_toml);
+                document.set_sections(sections);
+                document.blend_error = blend_error;
+            }
+            Err(error) => println!(
+                "Failed to read material {} with error {error}",
+                document.shader_path.to_string_lossy()
+            ),
+        }
+        document.saved_sections = document.sections().normalized();
+        document.shader_modified = modified_time(&document.shader_path);
+
+        document.texture_assignments = material::load_texture_assignments(&document.shader_path);
+        document.saved_texture_assignments = document.texture_assignments.clone();
+    }
+
+    // Makes the document the one the engine previews, on the sprite assigned to it
+    fn activate_document(&mut self, index: usize) {
+        self.active_document = index;
+        // Texture picks belong to the tab they were started from
+        self.texture_browser = None;
+        self.outside_asset_texture = None;
+
+        self.pending_commands.push_back(format!(
+            "set_preview_entity##DELIM##{}##DELIM##",
+            self.document().preview_entity
+        ));
+        self.queue_texture_loads();
+        if self.document().sections().validate().is_empty() {
+            self.queue_compile();
+        }
+    }
+
+    fn new_document(&mut self) {
+        let preview_entity = self.document().preview_entity;
+        self.documents
+            .push(MaterialDocument::new(self.next_document_id, preview_entity));
+        self.next_document_id += 1;
+        self.active_document = self.documents.len() - 1;
+    }
+
+    fn close_document(&mut self, index: usize) {
+        self.documents.remove(index);
+        if self.documents.is_empty() {
+            self.documents.push(MaterialDocument::new(self.next_document_id, 0));
+            self.next_document_id += 1;
+        }
 
-                self.uniforms_text = snippet[..snippet_end].trim_start().trim_end().to_string();
+        match index.cmp(&self.active_document) {
+            CmpOrdering::Less => self.active_document -= 1,
+            CmpOrdering::Equal => self.activate_document(index.min(self.documents.len() - 1)),
+            CmpOrdering::Greater => {}
+        }
+    }
+
+    // Tab bar, with the preview sprite the active tab is shown on
+    fn show_tabs(&mut self, ui: &mut egui::Ui) {
+        let mut activated = None;
+        let mut closed = None;
+
+        ui.horizontal_wrapped(|ui| {
+            for (index, document) in self.documents.iter().enumerate() {
+                let dirty_sections = document.dirty_sections();
+                let mut hover_text = document.shader_path.to_string_lossy().to_string();
+                if dirty_sections.any() {
+                    hover_text += &format!("\nUnsaved: {}", dirty_sections.names().join(", "));
+                }
+
+                let tab = ui
+                    .selectable_label(index == self.active_document, document.title())
+                    .on_hover_text(hover_text);
+                if tab.clicked() && index != self.active_document {
+                    activated = Some(index);
+                }
+                if ui.small_button("x").on_hover_text("Close").clicked() {
+                    closed = Some(index);
+                }
+                ui.separator();
+            }
+            if ui.button("+").on_hover_text("New material").clicked() {
+                self.new_document();
+            }
+
+            if self.preview_entity_count > 1 {
+                let mut preview_entity = self.document().preview_entity;
+                egui::ComboBox::from_id_salt("preview_entity")
+                    .selected_text(format!("Preview {}", preview_entity + 1))
+                    .show_ui(ui, |ui| {
+                        for entity in 0..self.preview_entity_count {
+                            ui.selectable_value(&mut preview_entity, entity, format!("Preview {}", entity + 1));
+                        }
+                    });
+                if preview_entity != self.document().preview_entity {
+                    self.document_mut().preview_entity = preview_entity;
+                    self.activate_document(self.active_document);
+                }
             }
+        });
 
-            let key = "[texture_descs]";
-            if let Some(snippet_key_idx) = material_toml.find(key) {
-                let snippet_start = snippet_key_idx + key.len();
-                let snippet = &material_toml[snippet_start..];
-                let snippet_end = snippet.find('[').unwrap_or(snippet.len());
+        if let Some(index) = closed {
+            self.request_close_document(index);
+        } else if let Some(index) = activated {
+            self.activate_document(index);
+        }
+    }
 
-                self.textures_text = snippet[..snippet_end].trim_start().trim_end().to_string();
+    // Closes the tab, asking first when it has unsaved edits
+    fn request_close_document(&mut self, index: usize) {
+        if self.documents[index].has_unsaved_edits() {
+            self.close_request = Some(CloseRequest::Document(self.documents[index].id));
+        } else {
+            self.close_document(index);
+        }
+    }
+
+    // Returns whether a material was opened, which adds it to the recent files
+    fn pick_material(&mut self) -> bool {
+        let file_picker = rfd::FileDialog::new()
+            .set_directory(&get_config().shader_directory.canonicalize().unwrap_or("./".into()));
+        match file_picker.pick_file() {
+            Some(file_path) => {
+                self.open_material(&file_path);
+                true
             }
+            None => false,
+        }
+    }
 
-            let key = "get_world_offset";
-            if let Some(snippet_key_idx) = material_toml.find(key) {
-                let snippet = &material_toml[snippet_key_idx..];
-                let start = snippet.find("\"\"\"").unwrap();
-                let end = snippet[start + 3..].find("\"\"\"").unwrap();
+    // Runs an action picked from the command palette or bound to a shortcut
+    fn run_action(&mut self, ctx: &egui::Context, action: Action, save_config: &mut bool) {
+        match action {
+            Action::Compile => self.queue_compile(),
+            Action::Save => {
+                self.save_document(self.active_document);
+            }
+            Action::SaveAs => {
+                self.save_document_as(self.active_document);
+            }
+            Action::Load => *save_config |= self.pick_material(),
+            Action::Undo => {
+                if let UndoAction::Restore(snapshot) = self.document_mut().undo_history.undo() {
+                    self.restore_snapshot(snapshot);
+                }
+            }
+            Action::Redo => {
+                if let UndoAction::Restore(snapshot) = self.document_mut().undo_history.redo() {
+                    self.restore_snapshot(snapshot);
+                }
+            }
+            Action::ToggleLive => self.live_mode = !self.live_mode,
+            Action::NewMaterial => self.new_document(),
+            Action::CloseMaterial => self.request_close_document(self.active_document),
+            Action::NextTab | Action::PreviousTab if self.documents.len() > 1 => {
+                let step = if action == Action::NextTab { 1 } else { self.documents.len() - 1 };
+                self.activate_document((self.active_document + step) % self.documents.len());
+            }
+            Action::NextTab | Action::PreviousTab => {}
+            Action::ToggleWorkspace => {
+                get_config().panel_layout.toggle("workspace_panel");
+                *save_config = true;
+            }
+            Action::FindReplace => self.find_replace.open(find::word_at_cursor(ctx, self.document())),
+            Action::RenameSymbol => self.rename_symbol.open(find::word_at_cursor(ctx, self.document())),
+            Action::ResetLayout => {
+                get_config().panel_layout.reset();
+                *save_config = true;
+            }
+            Action::CommandPalette => self.command_palette.open(),
+            Action::EditShortcuts => self.shortcut_editor.is_open = true,
+        }
+    }
 
-                self.world_offset_text = snippet[start + 3..start + 3 + end]
-                    .trim_start()
-                    .trim_end()
-                    .to_string();
+    // Contents of the dockable panels, see layout.rs
+    fn show_panel(
+        &mut self,
+        ui: &mut egui::Ui,
+        panel: Panel,
+        part: PanelPart,
+        symbols: &Symbols,
+        cmd_string: &mut String,
+        save_config: &mut bool,
+    ) {
+        match part {
+            PanelPart::Title => {
+                let dirty_sections = self.document().dirty_sections();
+                let dirty = match panel {
+                    Panel::Uniforms => dirty_sections.uniforms,
+                    Panel::Textures => dirty_sections.textures || dirty_sections.texture_assignments,
+                    Panel::Snippets => dirty_sections.world_offset || dirty_sections.frag_color,
+                    Panel::Parameters => dirty_sections.blend,
+                    Panel::Diagnostics => false,
+                };
+                section_label(ui, panel.name(), dirty);
             }
+            PanelPart::Contents => match panel {
+                Panel::Uniforms => self.show_uniforms_panel(ui, symbols),
+                Panel::Textures => self.show_textures_panel(ui, symbols, save_config),
+                Panel::Snippets => self.show_snippets_panel(ui, symbols),
+                Panel::Parameters => self.show_parameters_panel(ui, cmd_string),
+                Panel::Diagnostics => self.show_diagnostics_panel(ui, save_config),
+            },
+        }
+    }
 
-            let key = "get_fragment_color";
-            if let Some(snippet_key_idx) = material_toml.find(key) {
-                let snippet = &material_toml[snippet_key_idx..];
-                let start = snippet.find("\"\"\"").unwrap();
-                let end = snippet[start + 3..].find("\"\"\"").unwrap();
+    fn show_uniforms_panel(&mut self, ui: &mut egui::Ui, symbols: &Symbols) {
+        // Convenience buttons for adding new uniform variables
+        ui.horizontal_wrapped(|ui| {
+            let document = self.document_mut();
+            if ui.button("Add Vec4").clicked() {
+                if !document.uniforms_text.is_empty() {
+                    document.uniforms_text += "\n";
+                }
+                document.uniforms_text += "temp_vec4_var = { type = \"vec4f\", default = [1.0, 1.0, 1.0, 1.0] }";
+            }
+            if ui.button("Add f32").clicked() {
+                if !document.uniforms_text.is_empty() {
+                    document.uniforms_text += "\n";
+                }
+                document.uniforms_text += "temp_f32_var = { type = \"f32\", default = 1.2 }";
+            }
+            if ui.button("Add Add Array").clicked() {
+                if !document.uniforms_text.is_empty() {
+                    document.uniforms_text += "\n";
+                }
+                document.uniforms_text += "temp_array_var = { type = \"array<vec4f, 3>\", default = [\n\t[1.0, 0.8, 0.6, 1.0],\n\t[0.5, 0.7, 0.9, 1.0],\n\t[0.1, 0.2, 0.3, 1.0],\n] }";
+            }
+        });
+
+        // The editor fills the rest of the panel
+        let document_id = self.document().id;
+        ScrollArea::vertical()
+            .id_salt("uniform_scroll")
+            .auto_shrink(false)
+            .show(ui, |ui| {
+                let mut layouter = |ui: &egui::Ui, text: &str, wrap_width: f32| {
+                    highlight::layout(ui, text, wrap_width, Language::Toml, symbols)
+                };
+                let min_size = ui.available_size();
+                ui.add(
+                    TextEdit::multiline(&mut self.document_mut().uniforms_text)
+                        .id(Section::Uniforms.editor_id(document_id))
+                        .code_editor()
+                        .layouter(&mut layouter)
+                        .desired_width(f32::INFINITY)
+                        .font(egui::TextStyle::Monospace)
+                        .min_size(min_size),
+                );
+            });
+    }
+
+    fn show_textures_panel(&mut self, ui: &mut egui::Ui, symbols: &Symbols, save_config: &mut bool) {
+        let document_id = self.document().id;
+        let dirty_sections = self.document().dirty_sections();
+
+        ScrollArea::vertical()
+            .id_salt("texture_scroll")
+            .auto_shrink(false)
+            .show(ui, |ui| {
+                let mut layouter = |ui: &egui::Ui, text: &str, wrap_width: f32| {
+                    highlight::layout(ui, text, wrap_width, Language::Toml, symbols)
+                };
+                ui.add(
+                    TextEdit::multiline(&mut self.document_mut().textures_text)
+                        .id(Section::Textures.editor_id(document_id))
+                        .code_editor()
+                        .layouter(&mut layouter)
+                        .desired_width(f32::INFINITY)
+                        .font(egui::TextStyle::Monospace)
+                        .desired_rows(6),
+                );
+                if ui.button("Add Texture").clicked() {
+                    let document = self.document_mut();
+                    if !document.textures_text.is_empty() {
+                        document.textures_text += "\n";
+                    }
+                    document.textures_text += &format!(
+                        "{} = {}",
+                        material::unused_texture_binding_name(&document.textures_text),
+                        SamplerDesc::default().to_toml()
+                    );
+                }
+
+                // Texture bindings declared in [texture_descs]
+                ui.add_space(12.);
+                section_label(ui, "Texture Bindings", dirty_sections.texture_assignments);
+                if let Some(asset_root) = &self.asset_root {
+                    ui.label(format!("Asset root: {}", asset_root.to_string_lossy()));
+                }
+                let texture_bindings = material::parse_texture_bindings(&self.document().textures_text);
+                if texture_bindings.is_empty() {
+                    ui.label("No textures declared in [texture_descs]");
+                }
+                Grid::new("texture_bindings")
+                    .num_columns(7)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for (slot, binding) in texture_bindings.iter().enumerate().take(MAX_TEXTURES) {
+                            let assigned_texture = self.document().texture_assignments.get(&binding.name).cloned();
+                            let resolved_texture = assigned_texture
+                                .as_ref()
+                                .map(|texture_path| self.resolve_texture_path(texture_path));
+
+                            match &resolved_texture {
+                                Some(texture_path) => {
+                                    ui.add(textures::thumbnail(texture_path, textures::THUMBNAIL_SIZE))
+                                        .on_hover_ui(|ui| {
+                                            ui.add(textures::thumbnail(texture_path, 256.));
+                                        });
+                                }
+                                None => {
+                                    ui.allocate_space(egui::vec2(textures::THUMBNAIL_SIZE, textures::THUMBNAIL_SIZE));
+                                }
+                            }
+                            ui.label(&binding.name);
+                            match binding.sampler.clone() {
+                                Ok(mut sampler) => {
+                                    if textures::sampler_editor(ui, &binding.name, &mut sampler) {
+                                        let document = self.document_mut();
+                                        document.textures_text = material::set_texture_binding_sampler(
+                                            &document.textures_text,
+                                            &binding.name,
+                                            &sampler,
+                                        );
+                                    }
+                                }
+                                Err(error) => {
+                                    ui.colored_label(ui.visuals().error_fg_color, "Invalid sampler")
+                                        .on_hover_text(error);
+                                }
+                            }
+                            match &assigned_texture {
+                                Some(texture_path) => {
+                                    let file_name = texture_path
+                                        .file_name()
+                                        .unwrap_or_default()
+                                        .to_string_lossy()
+                                        .to_string();
+                                    if texture_path.is_absolute() && self.asset_root.is_some() {
+                                        ui.colored_label(ui.visuals().warn_fg_color, file_name)
+                                            .on_hover_text("Outside of the asset tree, referenced by absolute path");
+                                    } else {
+                                        ui.label(file_name)
+                                            .on_hover_text(texture_path.to_string_lossy());
+                                    }
+                                }
+                                None => {
+                                    ui.label("<none>");
+                                }
+                            }
+                            ui.label(
+                                resolved_texture
+                                    .as_ref()
+                                    .and_then(|texture_path| self.texture_infos.get(texture_path))
+                                    .map(|info| info.summary())
+                                    .unwrap_or_default(),
+                            );
 
-                self.frag_color_text = snippet[start + 3..start + 3 + end]
-                    .trim_start()
-                    .trim_end()
-                    .to_string();
+                            if ui.button("Pick...").clicked() {
+                                self.texture_browser = Some(TextureBrowser::new(
+                                    slot,
+                                    binding.name.clone(),
+                                    Self::texture_directory(slot),
+                                ));
+                            }
+
+                            if ui
+                                .add_enabled(resolved_texture.is_some(), egui::Button::new("Channels"))
+                                .clicked()
+                            {
+                                self.channel_viewer = resolved_texture.map(ChannelViewer::new);
+                            }
+                            ui.end_row();
+                        }
+                    });
+
+                // Procedural test textures
+                let header = egui::CollapsingHeader::new("Texture Generator")
+                    .id_salt("texture_generator_header")
+                    .default_open(get_config().panel_layout.is_open("texture_generator_header"))
+                    .show(ui, |ui| {
+                        if let Some((slot, texture_path)) =
+                            self.texture_generator.show(ui, &texture_bindings)
+                        {
+                            self.assign_generated_texture(slot, &texture_bindings[slot].name, &texture_path);
+                        }
+                    });
+                *save_config |= remember_header(&header.header_response, "texture_generator_header");
+
+                let texture_memory: u64 = self
+                    .engine_textures
+                    .iter()
+                    .map(|engine_texture| engine_texture.size_in_bytes)
+                    .sum();
+                let header = egui::CollapsingHeader::new(format!(
+                    "Loaded Textures ({}, {})",
+                    self.engine_textures.len(),
+                    textures::format_file_size(texture_memory)
+                ))
+                .id_salt("loaded_textures_header")
+                .default_open(get_config().panel_layout.is_open("loaded_textures_header"))
+                .show(ui, |ui| {
+                    textures::show_engine_textures(ui, &self.engine_textures);
+                });
+                *save_config |= remember_header(&header.header_response, "loaded_textures_header");
+            });
+    }
+
+    // World offset above fragment color, split by a draggable separator
+    fn show_snippets_panel(&mut self, ui: &mut egui::Ui, symbols: &Symbols) {
+        let document_id = self.document().id;
+        let dirty_sections = self.document().dirty_sections();
+        let panel_layout = &get_config().panel_layout;
+        let saved_height = panel_layout.world_offset_height;
+
+        let response = egui::TopBottomPanel::top(panel_layout.panel_id("world_offset_split"))
+            .resizable(true)
+            .default_height(saved_height)
+            .frame(egui::Frame::NONE)
+            .show_inside(ui, |ui| {
+                section_label(ui, "World Offset", dirty_sections.world_offset);
+                self.show_snippet_editor(ui, Section::WorldOffset, document_id, symbols);
+            });
+        let height = response.response.rect.height();
+        if (height - saved_height).abs() > layout::RESIZE_TOLERANCE {
+            get_config().panel_layout.world_offset_height = height;
+            self.layout_changed = true;
+        }
+
+        egui::CentralPanel::default()
+            .frame(egui::Frame::NONE)
+            .show_inside(ui, |ui| {
+                section_label(ui, "Fragment Color", dirty_sections.frag_color);
+                self.show_snippet_editor(ui, Section::FragColor, document_id, symbols);
+            });
+    }
+
+    // Wgsl editor with completions, filling the rest of `ui`
+    fn show_snippet_editor(&mut self, ui: &mut egui::Ui, section: Section, document_id: u64, symbols: &Symbols) {
+        let editor_id = section.editor_id(document_id);
+        ScrollArea::vertical()
+            .id_salt(editor_id.with("scroll"))
+            .auto_shrink(false)
+            .show(ui, |ui| {
+                let mut layouter = |ui: &egui::Ui, text: &str, wrap_width: f32| {
+                    highlight::layout(ui, text, wrap_width, Language::Wgsl, symbols)
+                };
+                let min_size = ui.available_size();
+                let text = section.text_mut(&mut self.documents[self.active_document]);
+                self.autocomplete.handle_keys(ui.ctx(), editor_id, text);
+                let output = TextEdit::multiline(text)
+                    .id(editor_id)
+                    .code_editor()
+                    .layouter(&mut layouter)
+                    .desired_width(f32::INFINITY)
+                    .font(egui::TextStyle::Monospace)
+                    .min_size(min_size)
+                    .show(ui);
+                self.autocomplete.show(ui, editor_id, text, &output, symbols);
+            });
+    }
+
+    fn show_parameters_panel(&mut self, ui: &mut egui::Ui, cmd_string: &mut String) {
+        ScrollArea::vertical()
+            .id_salt("parameters_scroll")
+            .auto_shrink(false)
+            .show(ui, |ui| {
+                // Blend state the preview pipeline is registered with
+                blend::blend_editor(ui, &mut self.document_mut().blend);
+                if let Some(pipeline_target) = &mut self.pipeline_target {
+                    ui.horizontal(|ui| {
+                        if pipeline_target.show(ui, &self.render_targets) {
+                            self.pending_commands.push_back(pipeline_target.command());
+                        }
+                        if !pipeline_target.is_scene(&self.render_targets) {
+                            ui.label("Not the scene target, compiles are validated but not previewed");
+                        }
+                    });
+                }
+
+                // Material parameter widgets
+                ui.add_space(12.);
+                ui.horizontal_wrapped(|ui| {
+                    // Extracts variable data from [uniform_types].
+                    // ex: given temp_vec4_var = { type = "vec4f", default = [1.0, 1.0, 1.0, 1.0] },
+                    //  captures `temp_vec4_var` into `var_name`, and `[1.0, 1.0, 1.0, 1.0]` into `color_vec`
+                    let mut changed_uniform = None;
+
+                    let document = &mut self.documents[self.active_document];
+                    document.uniforms_text = material::VEC4_UNIFORM.replace_all(&document.uniforms_text, |caps: &regex::Captures| {
+                        let default_value = caps.get(2).map(|m| m.as_str()).unwrap_or("1., 1., 1., 1.");
+                        let mut color: [f32;4] = {
+                            let color_vec: Vec<f32>  = default_value
+                                .split(',')
+                                .map(|s| s.trim().parse::<f32>().unwrap_or(1.))
+                                .collect();
+
+                            color_vec.try_into().unwrap_or([1., 1., 1., 1.])
+                        };
+
+                        let var_name = &caps[1];
+                        ui.label(format!("{}:", var_name));
+
+                        let prev_color = color;
+                        let _color_picker = ui.color_edit_button_rgba_unmultiplied(&mut color);
+                        let color_str = format!("[{:.1}, {:.1}, {:.1}, {:.1}]", color[0], color[1], color[2], color[3]);
+                        if prev_color != color {
+                            changed_uniform = Some(var_name.to_string());
+                        }
+                        if prev_color != color && cmd_string.is_empty() {
+                            *cmd_string = format!(
+                                "update_uniform##DELIM##{}##DELIM##{color_str}##DELIM##", var_name
+                            );
+                        }
+
+                        // Update the uniform in this match with the update color value
+                        format!(r#"{} = {{ type = "vec4f", default = {color_str}"#, var_name)
+                    }).to_string();
+
+                    if let Some(changed_uniform) = changed_uniform {
+                        document.undo_history.label_next_change(format!("Change {changed_uniform}"));
+                    }
+                });
+            });
+    }
+
+    fn show_diagnostics_panel(&mut self, ui: &mut egui::Ui, save_config: &mut bool) {
+        ScrollArea::vertical()
+            .id_salt("diagnostics_scroll")
+            .auto_shrink(false)
+            .show(ui, |ui| {
+                for validation_error in &self.document().validation_errors {
+                    ui.colored_label(ui.visuals().error_fg_color, validation_error);
+                }
+                if let Some(blend_error) = &self.document().blend_error {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        format!("Invalid [blend_state], using alpha blending: {blend_error}"),
+                    );
+                }
+                if !self.status_text.is_empty() {
+                    ui.label(&self.status_text);
+                }
+                if let Some(editor_material_count) = self.editor_material_count {
+                    ui.label(format!("Editor materials registered: {editor_material_count}"))
+                        .on_hover_text("Superseded compiles stay registered with the engine");
+                }
+
+                let header = egui::CollapsingHeader::new(format!("Compile History ({})", self.compile_history.len()))
+                    .id_salt("compile_history_header")
+                    .default_open(get_config().panel_layout.is_open("compile_history_header"))
+                    .show(ui, |ui| {
+                        if let HistoryAction::Restore(sections) = self.compile_history.show(ui) {
+                            self.restore_sections(sections);
+                        }
+                    });
+                *save_config |= remember_header(&header.header_response, "compile_history_header");
+
+                let header = egui::CollapsingHeader::new(format!("Undo History ({})", self.document().undo_history.len()))
+                    .id_salt("undo_history_header")
+                    .default_open(get_config().panel_layout.is_open("undo_history_header"))
+                    .show(ui, |ui| {
+                        let undo_action = self.document_mut().undo_history.show(ui, &get_config().key_bindings);
+                        if let UndoAction::Restore(snapshot) = undo_action {
+                            self.restore_snapshot(snapshot);
+                        }
+                    });
+                *save_config |= remember_header(&header.header_response, "undo_history_header");
+            });
+    }
+
+    // Sends every assigned texture to the engine so the preview matches the active material
+    fn queue_texture_loads(&mut self) {
+        let document = &self.documents[self.active_document];
+        for (slot, binding) in material::parse_texture_bindings(&document.textures_text)
+            .iter()
+            .enumerate()
+        {
+            if let Some(texture_path) = document.texture_assignments.get(&binding.name) {
+                self.pending_commands
+                    .push_back(load_texture_command(texture_path, slot));
             }
         }
     }
 
-    fn save_material(&self, file_path: &PathBuf) {
-        if let Ok(mut file) = File::create(file_path) {
-            let toml_mat = format!(
-                "get_world_offset = \"\"\"\n{}\n\"\"\"\n\nget_fragment_color = \"\"\"\n{}\"\"\"\n\n[uniform_types]\n{}\n\n[texture_descs]\n{}\n",
-                self.world_offset_text
-                    .trim_start()
-                    .trim_end(),
-                self.frag_color_text
-                    .trim_start()
-                    .trim_end(),
-                self.uniforms_text
-                    .trim_start()
-                    .trim_end(),
-                self.textures_text
-                    .trim_start()
-                    .trim_end()
-            ).replace("\r", "\n");
+    // Saves to the document's own file, asking for one if it hasn't been saved yet
+    fn save_document(&mut self, index: usize) -> bool {
+        if self.documents[index].is_untitled() {
+            return self.save_document_as(index);
+        }
+
+        let shader_path = self.documents[index].shader_path.clone();
+        self.save_material(index, &shader_path)
+    }
+
+    fn save_document_as(&mut self, index: usize) -> bool {
+        let file_picker = FileDialog::new()
+            .set_title("Save Material")
+            .set_directory(get_config().shader_directory.canonicalize().unwrap_or("./".into()))
+            .set_file_name(self.documents[index].shader_path.file_name().unwrap_or(&std::ffi::OsString::from("./")).to_string_lossy())
+            .save_file();
+
+        if let Some(save_file_path) = file_picker {
+            self.save_material(index, &save_file_path)
+        } else {
+            println!("Failed to save material");
+            false
+        }
+    }
+
+    // The document takes on the path it is saved to, like a Save As in any other editor
+    fn save_material(&mut self, index: usize, file_path: &PathBuf) -> bool {
+        let document = &mut self.documents[index];
+        let Ok(mut file) = File::create(file_path) else {
+            println!("Failed to create material {}", file_path.to_string_lossy());
+            return false;
+        };
+        let toml_mat = document.sections().to_toml();
+
+        if let Err(result) = file.write_all(toml_mat.as_bytes()) {
+            println!(
+                "Failed to write material {} with error {}",
+                file_path.to_string_lossy(),
+                result.to_string()
+            );
+            return false;
+        }
+        println!("Saved material {}", file_path.to_string_lossy());
+
+        if *file_path != document.shader_path {
+            document.undo_history.label_next_change(format!(
+                "Save as {}",
+                file_path.file_name().unwrap_or_default().to_string_lossy()
+            ));
+            document.shader_path = file_path.clone();
+            get_config().add_recent_file(file_path);
+        }
+        document.saved_sections = document.sections().normalized();
+        document.shader_modified = modified_time(&document.shader_path);
+        document.saved_texture_assignments = document.texture_assignments.clone();
+        // The file now holds the blend state the editor shows
+        document.blend_error = None;
+
+        material::save_texture_assignments(file_path, &document.texture_assignments);
+        true
+    }
+
+    // Asks whether to save documents with unsaved edits before closing their tab, or the window
+    fn show_close_prompt(&mut self, ctx: &egui::Context) {
+        let Some(close_request) = self.close_request else {
+            return;
+        };
+        let unsaved_documents: Vec<usize> = self
+            .documents
+            .iter()
+            .enumerate()
+            .filter(|(_, document)| match close_request {
+                CloseRequest::Document(id) => document.id == id,
+                CloseRequest::Window => document.has_unsaved_edits(),
+            })
+            .map(|(index, _)| index)
+            .collect();
+
+        egui::Window::new("Unsaved changes")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label("These materials have unsaved changes:");
+                for index in &unsaved_documents {
+                    let document = &self.documents[*index];
+                    ui.label(format!(
+                        "{} ({})",
+                        document.title(),
+                        document.dirty_sections().names().join(", ")
+                    ));
+                }
+
+                ui.horizontal(|ui| {
+                    if ui.button("Save").clicked() {
+                        let saved = unsaved_documents
+                            .iter()
+                            .all(|index| self.save_document(*index));
+                        if saved {
+                            self.close(ctx, close_request);
+                        }
+                    }
+                    if ui.button("Discard").clicked() {
+                        self.close(ctx, close_request);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.close_request = None;
+                    }
+                });
+            });
+    }
+
+    fn close(&mut self, ctx: &egui::Context, close_request: CloseRequest) {
+        self.close_request = None;
+        match close_request {
+            CloseRequest::Document(id) => {
+                if let Some(index) = self.documents.iter().position(|document| document.id == id) {
+                    self.close_document(index);
+                }
+            }
+            CloseRequest::Window => {
+                self.confirmed_close = true;
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            }
+        }
+    }
+
+    // Window title with the active material, marked while it has unsaved edits
+    fn update_window_title(&mut self, ctx: &egui::Context) {
+        let window_title = format!("Material Editor - {}", self.document().title());
+        if window_title != self.window_title {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Title(window_title.clone()));
+            self.window_title = window_title;
+        }
+    }
+}
+
+impl MaterialEditor {
+    fn assign_texture(&mut self, slot: usize, binding_name: &str, texture_path: PathBuf) {
+        self.pending_commands
+            .push_back(load_texture_command(&texture_path, slot));
+        self.document_mut()
+            .texture_assignments
+            .insert(binding_name.to_string(), texture_path);
+    }
+
+    // Generated textures are written to the temp directory and deleted once superseded, so they are
+    // copied into the asset tree before a material references them
+    fn assign_generated_texture(&mut self, slot: usize, binding_name: &str, texture_path: &Path) {
+        let Some(asset_root) = self.asset_root.clone() else {
+            // Only previewed, a temp path saved with the material would soon point at nothing
+            self.pending_commands
+                .push_back(load_texture_command(texture_path, slot));
+            self.status_text = "Generated texture is previewed but not assigned, the engine hasn't reported its asset root yet".to_string();
+            return;
+        };
+
+        match textures::copy_into_assets(&asset_root, texture_path) {
+            Ok(asset_path) => {
+                self.status_text = format!("Generated texture {}", asset_path.to_string_lossy());
+                self.assign_texture(slot, binding_name, asset_path);
+            }
+            Err(error) => {
+                self.status_text = format!(
+                    "Failed to copy {} into assets with error {error}",
+                    texture_path.to_string_lossy()
+                );
+            }
+        }
+    }
+
+    // Files picked from disk are assigned by asset-relative path, or the user is asked what to do
+    // when they lie outside the asset tree
+    fn pick_texture(&mut self, slot: usize, binding_name: &str, file_path: PathBuf) {
+        get_config().texture_directories[slot] = file_path
+            .parent()
+            .unwrap_or(PathBuf::from("./").as_path())
+            .to_path_buf();
+
+        match &self.asset_root {
+            Some(asset_root) => match textures::asset_relative_path(asset_root, &file_path) {
+                Some(asset_path) => self.assign_texture(slot, binding_name, asset_path),
+                None => {
+                    self.outside_asset_texture = Some((slot, binding_name.to_string(), file_path))
+                }
+            },
+            None => self.assign_texture(slot, binding_name, file_path),
+        }
+    }
+
+    // Location of an assigned texture on disk
+    fn resolve_texture_path(&self, texture_path: &Path) -> PathBuf {
+        match &self.asset_root {
+            Some(asset_root) if texture_path.is_relative() => asset_root.join(texture_path),
+            _ => texture_path.to_path_buf(),
+        }
+    }
+
+    fn show_outside_asset_prompt(&mut self, ctx: &egui::Context) {
+        let (Some((slot, binding_name, file_path)), Some(asset_root)) =
+            (self.outside_asset_texture.clone(), self.asset_root.clone())
+        else {
+            return;
+        };
 
-            if let Err(result) = file.write_all(toml_mat.as_bytes()) {
-                println!(
-                    "Failed to write material {} with error {}",
+        egui::Window::new("Texture outside of assets")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{} lies outside of the asset tree {}.",
                     file_path.to_string_lossy(),
-                    result.to_string()
+                    asset_root.to_string_lossy()
+                ));
+                ui.label("Scenes and materials can't reference it portably until it is copied in.");
+
+                ui.horizontal(|ui| {
+                    if ui.button("Copy into assets").clicked() {
+                        match textures::copy_into_assets(&asset_root, &file_path) {
+                            Ok(asset_path) => {
+                                self.status_text = format!("Copied texture to {}", asset_path.to_string_lossy());
+                                self.assign_texture(slot, &binding_name, asset_path);
+                            }
+                            Err(error) => {
+                                self.status_text = format!(
+                                    "Failed to copy {} into assets with error {error}",
+                                    file_path.to_string_lossy()
+                                );
+                            }
+                        }
+                        self.outside_asset_texture = None;
+                    }
+                    if ui.button("Use absolute path").clicked() {
+                        self.assign_texture(slot, &binding_name, file_path.clone());
+                        self.outside_asset_texture = None;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.outside_asset_texture = None;
+                    }
+                });
+            });
+    }
+
+    // Messages sent by the engine module, formatted like the commands we send it
+    fn handle_message(&mut self, ctx: &egui::Context, message: &str) {
+        let message: Vec<&str> = message.split("##DELIM##").collect();
+        match message[0] {
+            "asset_root" => {
+                println!("Gui - asset root {}", message[1]);
+                self.asset_root = Some(PathBuf::from(message[1]));
+            }
+            "texture_reloaded" => {
+                let texture_path = self.resolve_texture_path(Path::new(message[1]));
+                ctx.forget_image(&textures::texture_uri(&texture_path));
+                self.texture_infos.invalidate(&texture_path);
+                if self
+                    .channel_viewer
+                    .as_ref()
+                    .is_some_and(|channel_viewer| channel_viewer.path == texture_path)
+                {
+                    self.channel_viewer = Some(ChannelViewer::new(texture_path.clone()));
+                }
+
+                self.status_text = format!(
+                    "Reloaded {}",
+                    texture_path.file_name().unwrap_or_default().to_string_lossy()
                 );
-            } else {
-                println!("Saved material {}", file_path.to_string_lossy());
+            }
+            "compile_result" => {
+                // Replies come in order, compiles older than the answered one will never get theirs
+                let compile_id = message.get(3).and_then(|compile_id| compile_id.trim().parse::<u64>().ok());
+                let answered = self
+                    .in_flight_compiles
+                    .iter()
+                    .take_while(|(in_flight_id, _, _)| Some(*in_flight_id) <= compile_id)
+                    .count();
+                let compiled = self
+                    .in_flight_compiles
+                    .drain(..answered)
+                    .find(|(in_flight_id, _, _)| Some(*in_flight_id) == compile_id)
+                    .map(|(_, material_name, sections)| (material_name, sections));
+                match message[1] {
+                    "ok" => {
+                        self.compile_error = None;
+                        // The module builds for the scene until a target is picked here
+                        self.status_text = match &self.pipeline_target {
+                            Some(pipeline_target) if !pipeline_target.is_scene(&self.render_targets) => format!(
+                                "Compiled {} for {}, the preview keeps the last scene material",
+                                message[2],
+                                pipeline_target.label(&self.render_targets)
+                            ),
+                            _ => format!("Compiled {}", message[2]),
+                        };
+                        if let Some((material_name, sections)) = compiled {
+                            self.compile_history
+                                .record(HistoryEntry::new(material_name, sections));
+                        }
+                    }
+                    _ => self.compile_error = Some(message[2].to_string()),
+                }
+            }
+            "render_targets" => {
+                self.render_targets = render_target::parse_render_targets(message[1]);
+                let still_reported = self.pipeline_target.as_ref().is_some_and(|pipeline_target| {
+                    self.render_targets
+                        .iter()
+                        .any(|render_target| render_target.name == pipeline_target.render_target)
+                });
+                if !still_reported {
+                    self.pipeline_target = PipelineTarget::scene(&self.render_targets);
+                }
+                // The module starts out building for the scene, so restore the selection made here
+                if let Some(pipeline_target) = &self.pipeline_target {
+                    self.pending_commands.push_back(pipeline_target.command());
+                }
+            }
+            "preview_entities" => {
+                self.preview_entity_count = message[1].trim().parse().unwrap_or(1);
+            }
+            "material_stats" => {
+                self.editor_material_count = message[1].trim().parse().ok();
+            }
+            "loaded_textures" => {
+                self.engine_textures = textures::parse_engine_textures(message[1]);
+            }
+            _ => println!("Gui - unknown message {}", message[0]),
+        }
+    }
+
+    // Reloads the active material when another editor changes it, asking first if there are unsaved edits here.
+    // Other tabs are checked once they become active again
+    fn poll_material_file(&mut self) {
+        let document = self.document_mut();
+        if document.last_file_poll.elapsed() < MATERIAL_FILE_POLL_INTERVAL
+            || document.disk_conflict.is_some()
+            || !document.shader_path.is_file()
+        {
+            return;
+        }
+        document.last_file_poll = Instant::now();
+
+        let modified = modified_time(&document.shader_path);
+        if modified.is_none() || modified == document.shader_modified {
+            return;
+        }
+        document.shader_modified = modified;
+
+        let Ok(material_toml) = fs::read_to_string(&document.shader_path) else {
+            return;
+        };
+        let (disk_sections, _) = MaterialSections::from_toml(&material_toml);
+        if disk_sections == document.saved_sections {
+            return;
+        }
+
+        if document.has_unsaved_edits() {
+            document.disk_conflict = Some(disk_sections);
+        } else {
+            self.reload_from_disk(disk_sections);
+        }
+    }
+
+    fn reload_from_disk(&mut self, disk_sections: MaterialSections) {
+        let document = self.document_mut();
+        println!("Reloading material {}", document.shader_path.to_string_lossy());
+        document.saved_sections = disk_sections.clone();
+        document.undo_history.label_next_change("Reload from disk");
+        document.set_sections(disk_sections);
+        // Live mode compiles the reloaded sections itself once they validate
+        if !self.live_mode {
+            self.queue_compile();
+        }
+        self.status_text = format!(
+            "Reloaded {} from disk",
+            self.document().shader_path.file_name().unwrap_or_default().to_string_lossy()
+        );
+    }
+
+    fn show_disk_conflict_prompt(&mut self, ctx: &egui::Context) {
+        let Some(disk_sections) = self.document().disk_conflict.clone() else {
+            return;
+        };
+
+        egui::Window::new("Material changed on disk")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{} was changed by another program while it has unsaved edits here.",
+                    self.document().shader_path.to_string_lossy()
+                ));
+
+                ui.horizontal(|ui| {
+                    if ui.button("Reload from disk").clicked() {
+                        self.reload_from_disk(disk_sections.clone());
+                        self.document_mut().disk_conflict = None;
+                    }
+                    if ui.button("Keep my edits").clicked() {
+                        // The disk version becomes the saved state, so the edits here show as unsaved
+                        let document = self.document_mut();
+                        document.saved_sections = disk_sections.clone();
+                        document.disk_conflict = None;
+                    }
+                });
+            });
+    }
+
+    fn restore_snapshot(&mut self, snapshot: EditorSnapshot) {
+        let document = self.document_mut();
+        if snapshot.shader_path != document.shader_path {
+            // Undoing a load goes back to the previous file, compared against what is on disk for it
+            document.shader_path = snapshot.shader_path;
+            document.saved_sections = fs::read_to_string(&document.shader_path)
+                .map(|material_toml| MaterialSections::from_toml(&material_toml).0.normalized())
+                .unwrap_or_default();
+            document.shader_modified = modified_time(&document.shader_path);
+            document.saved_texture_assignments = material::load_texture_assignments(&document.shader_path);
+        }
+
+        let textures_changed = snapshot.texture_assignments != document.texture_assignments;
+        let previous_uniforms = document.uniforms_text.clone();
+        document.set_sections(snapshot.sections);
+        document.texture_assignments = snapshot.texture_assignments;
+
+        // The engine only follows edits through commands, so send what the restore changed.
+        // update_uniform only takes vec4 values, other defaults and declarations need a compile
+        let document = self.document_mut();
+        if material::without_vec4_defaults(&previous_uniforms) != material::without_vec4_defaults(&document.uniforms_text) {
+            let sections = document.sections();
+            document.validation_errors = sections.validate();
+            if document.validation_errors.is_empty() {
+                // Already sent, live mode doesn't need to compile it again
+                document.last_sections = sections;
+                document.last_edit = None;
+                self.queue_compile();
+            }
+        } else {
+            let previous_defaults = material::vec4_uniform_defaults(&previous_uniforms);
+            for (name, value) in material::vec4_uniform_defaults(&document.uniforms_text) {
+                if !previous_defaults.contains(&(name.clone(), value.clone())) {
+                    self.pending_commands
+                        .push_back(format!("update_uniform##DELIM##{name}##DELIM##{value}##DELIM##"));
+                }
             }
         }
+        if textures_changed {
+            self.queue_texture_loads();
+            let document = &self.documents[self.active_document];
+            for (slot, binding) in material::parse_texture_bindings(&document.textures_text)
+                .iter()
+                .enumerate()
+            {
+                if !document.texture_assignments.contains_key(&binding.name) {
+                    self.pending_commands
+                        .push_back(format!("unbind_texture##DELIM##{slot}##DELIM##"));
+                }
+            }
+        }
+    }
+
+    // Tracks edits to the active material and sends a compile once they settle in live mode
+    fn update_live_compile(&mut self) {
+        let live_mode = self.live_mode;
+        let document = self.document_mut();
+        let sections = document.sections();
+        if sections != document.last_sections {
+            document.validation_errors = sections.validate();
+            document.last_sections = sections;
+            document.last_edit = Some(Instant::now());
+        }
+
+        let Some(last_edit) = document.last_edit else {
+            return;
+        };
+        if !live_mode || last_edit.elapsed() < LIVE_COMPILE_DEBOUNCE {
+            return;
+        }
+
+        document.last_edit = None;
+        if document.validation_errors.is_empty() {
+            self.queue_compile();
+        }
+    }
+
+    fn queue_compile(&mut self) {
+        let document = &self.documents[self.active_document];
+        let compile_id = self.next_compile_id;
+        self.next_compile_id += 1;
+        self.pending_commands.push_back(document.compile_command(compile_id));
+        self.in_flight_compiles
+            .push_back((compile_id, document.material_name(), document.sections()));
     }
+
+    fn restore_sections(&mut self, sections: MaterialSections) {
+        let document = self.document_mut();
+        document.undo_history.label_next_change("Restore compiled version");
+        document.set_sections(sections);
+        self.queue_compile();
+    }
+
+    // Shown over the editor while the latest compile failed
+    fn show_compile_error_overlay(&mut self, ctx: &egui::Context) {
+        let Some(compile_error) = &self.compile_error else {
+            return;
+        };
+
+        let mut dismissed = false;
+        egui::Area::new(egui::Id::new("compile_error_overlay"))
+            .anchor(egui::Align2::CENTER_TOP, [0., 8.])
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style())
+                    .fill(ui.visuals().extreme_bg_color)
+                    .stroke(egui::Stroke::new(2., ui.visuals().error_fg_color))
+                    .show(ui, |ui| {
+                        ui.set_max_width(ctx.screen_rect().width() - 48.);
+                        ui.colored_label(
+                            ui.visuals().error_fg_color,
+                            if self.keep_last_good {
+                                "Compile failed, the preview keeps the last good material"
+                            } else {
+                                "Compile failed"
+                            },
+                        );
+                        egui::CollapsingHeader::new("Details")
+                            .id_salt("compile_error_details")
+                            .show(ui, |ui| {
+                                ScrollArea::vertical().max_height(160.).show(ui, |ui| {
+                                    ui.monospace(compile_error);
+                                });
+                            });
+                        ui.horizontal(|ui| {
+                            if self.keep_last_good && ui.button("Show broken material").clicked() {
+                                self.pending_commands
+                                    .push_back("show_broken##DELIM##".to_string());
+                            }
+                            if ui.button("Dismiss").clicked() {
+                                dismissed = true;
+                            }
+                        });
+                    });
+            });
+
+        if dismissed {
+            self.compile_error = None;
+        }
+    }
+
+    fn texture_directory(slot: usize) -> PathBuf {
+        get_config().texture_directories[slot]
+            .canonicalize()
+            .unwrap_or("./".into())
+    }
+}
+
+// Keeps the open state of a collapsing header in the panel layout, returns whether it changed
+fn remember_header(header_response: &egui::Response, id_salt: &str) -> bool {
+    if !header_response.clicked() {
+        return false;
+    }
+
+    get_config().panel_layout.toggle(id_salt);
+    true
+}
+
+// Section heading, marked while the section differs from the saved file
+fn section_label(ui: &mut egui::Ui, label: &str, dirty: bool) {
+    ui.horizontal(|ui| {
+        ui.label(label);
+        if dirty {
+            ui.colored_label(ui.visuals().warn_fg_color, "*")
+                .on_hover_text("Unsaved changes");
+        }
+    });
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+fn load_texture_command(texture_path: &Path, slot: usize) -> String {
+    format!(
+        "load_texture##DELIM##{}##DELIM##{slot}##DELIM##",
+        texture_path.to_string_lossy()
+    )
 }
 
 impl Default for MaterialEditor {
     fn default() -> Self {
         Self {
-            shader_path: env::current_dir().unwrap_or("./".into()),
-            textures: std::array::from_fn(|_| String::new()),
-            uniforms_text: "".to_string(),
-            textures_text: "".to_string(),
-            world_offset_text: "".to_string(),
-            frag_color_text: "".to_string(),
+            documents: vec![MaterialDocument::new(0, 0)],
+            active_document: 0,
+            next_document_id: 1,
+            preview_entity_count: 1,
+            asset_root: None,
+            outside_asset_texture: None,
+            texture_infos: TextureInfoCache::default(),
+            texture_browser: None,
+            channel_viewer: None,
+            texture_generator: TextureGenerator::default(),
+            engine_textures: Vec::new(),
+            editor_material_count: None,
+            live_mode: false,
+            in_flight_compiles: VecDeque::new(),
+            next_compile_id: 0,
+            compile_history: CompileHistory::load(),
+            render_targets: Vec::new(),
+            pipeline_target: None,
+            keep_last_good: true,
+            compile_error: None,
+            status_text: String::new(),
+            pending_commands: VecDeque::new(),
+            autocomplete: Autocomplete::default(),
+            workspace: Workspace::new(get_config().workspace_directory()),
+            layout_changed: false,
+            command_palette: CommandPalette::default(),
+            shortcut_editor: ShortcutEditor::default(),
+            find_replace: FindReplace::default(),
+            rename_symbol: RenameSymbol::default(),
+            close_request: None,
+            confirmed_close: false,
+            window_title: String::new(),
         }
     }
 }
@@ -171,219 +1410,292 @@ impl eframe::App for MaterialEditor {
         let mut cmd_string = String::new();
         let mut save_config = false;
 
-        CentralPanel::default().show(ctx, |ui| {
-            let available_rect = ctx.available_rect();
-            let usable_width = ui.max_rect().width() - ui.spacing().item_spacing.x;
-            let usable_height = ui.max_rect().height() - ui.spacing().item_spacing.y;
+        // Handled before any widget runs, so a focused text box doesn't also undo on its own
+        save_config |= self.shortcut_editor.record_key(ctx, &mut get_config().key_bindings);
+        if let Some(action) = get_config().key_bindings.consume(ctx) {
+            self.run_action(ctx, action, &mut save_config);
+        }
 
-            let text_height = 12_f32;
+        // Closing the window waits for the user to save or discard unsaved edits
+        if ctx.input(|input| input.viewport().close_requested())
+            && !self.confirmed_close
+            && self.documents.iter().any(MaterialDocument::has_unsaved_edits)
+        {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.close_request = Some(CloseRequest::Window);
+        }
 
-            ui.set_min_size(available_rect.size());
+        // The window reopens where it was, its geometry is saved along with the settings on close
+        let (outer_rect, inner_rect, maximized) = ctx.input(|input| {
+            let viewport = input.viewport();
+            (viewport.outer_rect, viewport.inner_rect, viewport.maximized.unwrap_or(false))
+        });
+        let window = &mut get_config().window;
+        window.maximized = maximized;
+        if let (Some(outer_rect), Some(inner_rect), false) = (outer_rect, inner_rect, maximized) {
+            window.position = [outer_rect.min.x, outer_rect.min.y];
+            window.size = [inner_rect.width(), inner_rect.height()];
+        }
+        if ctx.input(|input| input.viewport().close_requested()) && self.close_request.is_none() {
+            save_config = true;
+        }
 
-            // Load/Save Buttons
-            ui.horizontal(|ui| {
-                let file_button = ui.button("Load:");
-                if file_button.clicked() {
-                    let file_picker = rfd::FileDialog::new()
-                        .set_directory(&get_config().shader_directory.canonicalize().unwrap_or("./".into()));
-                    if let Some(file_path) = file_picker.pick_file() {
-                        self.load_material(&file_path);
+        // Workspace materials open with a single click
+        egui::SidePanel::left("workspace_panel")
+            .resizable(true)
+            .default_width(200.)
+            .show_animated(ctx, get_config().panel_layout.is_open("workspace_panel"), |ui| {
+                let open_materials: Vec<&Path> = self
+                    .documents
+                    .iter()
+                    .map(|document| document.shader_path.as_path())
+                    .collect();
+                let active_material = self.documents[self.active_document].shader_path.as_path();
+
+                match self.workspace.show(ui, &open_materials, active_material) {
+                    WorkspaceAction::None => {}
+                    WorkspaceAction::Open(file_path) => {
+                        self.open_material(&file_path);
                         save_config = true;
                     }
-                }
-                ui.text_edit_singleline(&mut self.shader_path.to_str().unwrap());
-
-                let save_button = ui.button("Save:");
-                if save_button.clicked() {
-                    let file_picker = FileDialog::new()
-                        .set_title("Save Material")
-                        .set_directory(&get_config().shader_directory.canonicalize().unwrap())
-                        .set_file_name(self.shader_path.file_name().unwrap_or(&std::ffi::OsString::from("./")).to_string_lossy())
-                        .save_file();
-
-                    if let Some(save_file_path) = file_picker {
-                        self.save_material(&save_file_path);
-                    } else {
-                        println!("Failed to save material");
+                    WorkspaceAction::ChooseDirectory => {
+                        let directory_picker = FileDialog::new()
+                            .set_title("Workspace Directory")
+                            .set_directory(&self.workspace.directory);
+                        if let Some(directory) = directory_picker.pick_folder() {
+                            self.workspace.set_directory(&directory);
+                            get_config().workspace_directory = Some(directory);
+                            save_config = true;
+                        }
                     }
                 }
             });
 
-            // Uniforms and texture text snippets
-            ui.add_space(text_height * 2.);
-            ui.label("Uniforms:");
-            ScrollArea::vertical()
-                .id_salt("uniform_scroll")
-                .max_width(usable_width)
-                .max_height(75.)
-                .show(ui, |ui| {
-                    ui.add(
-                        TextEdit::multiline(&mut self.uniforms_text)
-                            .code_editor()
-                            .desired_width(f32::INFINITY)
-                            .font(egui::TextStyle::Monospace)
-                            .desired_rows(10),
-                    );
-                });
+        let mut layout_changes = Vec::new();
+
+        // Open materials, file and compile controls stay on top whatever the panel layout
+        egui::TopBottomPanel::top("toolbar").show(ctx, |ui| {
+            self.show_tabs(ui);
 
-            // Convenience buttons for adding new uniform variables
-            ui.add_space(text_height);
+            // Load/Save Buttons
             ui.horizontal(|ui| {
-                if ui.button("Add Vec4").clicked() {
-                    if !self.uniforms_text.is_empty() {
-                        self.uniforms_text += "\n";
-                    }
-                    self.uniforms_text += "temp_vec4_var = { type = \"vec4f\", default = [1.0, 1.0, 1.0, 1.0] }";
+                let file_button = ui.button("Load:").on_hover_text(get_config().key_bindings.text(Action::Load));
+                if file_button.clicked() {
+                    save_config |= self.pick_material();
                 }
-                if ui.button("Add f32").clicked() {
-                    if !self.uniforms_text.is_empty() {
-                        self.uniforms_text += "\n";
+                ui.menu_button("Recent", |ui| {
+                    let recent_files = get_config().recent_files.clone();
+                    if recent_files.is_empty() {
+                        ui.weak("No recent materials");
+                    }
+                    for recent_file in recent_files {
+                        let file_name = recent_file.file_name().unwrap_or_default().to_string_lossy().to_string();
+                        if ui
+                            .add_enabled(recent_file.is_file(), egui::Button::new(file_name))
+                            .on_hover_text(recent_file.to_string_lossy())
+                            .on_disabled_hover_text(format!("{} no longer exists", recent_file.to_string_lossy()))
+                            .clicked()
+                        {
+                            self.open_material(&recent_file);
+                            save_config = true;
+                            ui.close_menu();
+                        }
                     }
-                    self.uniforms_text += "temp_f32_var = { type = \"f32\", default = 1.2 }";
+                    ui.separator();
+                    if ui.button("Clear").clicked() {
+                        get_config().recent_files.clear();
+                        save_config = true;
+                        ui.close_menu();
+                    }
+                });
+                let mut show_workspace = get_config().panel_layout.is_open("workspace_panel");
+                if ui.toggle_value(&mut show_workspace, "Workspace").clicked() {
+                    get_config().panel_layout.toggle("workspace_panel");
+                    save_config = true;
                 }
-                if ui.button("Add Add Array").clicked() {
-                    if !self.uniforms_text.is_empty() {
-                        self.uniforms_text += "\n";
+                ui.menu_button("Layout", |ui| {
+                    let panel_layout = get_config().panel_layout.clone();
+                    if layout::layout_menu(ui, &panel_layout, &mut layout_changes) {
+                        get_config().panel_layout.reset();
+                        save_config = true;
                     }
-                    self.uniforms_text += "temp_array_var = { type = \"array<vec4f, 3>\", default = [\n\t[1.0, 0.8, 0.6, 1.0],\n\t[0.5, 0.7, 0.9, 1.0],\n\t[0.1, 0.2, 0.3, 1.0],\n] }";
+                });
+                ui.text_edit_singleline(&mut self.document().shader_path.to_str().unwrap());
+
+                if ui.button("Save").on_hover_text(get_config().key_bindings.text(Action::Save)).clicked() {
+                    self.save_document(self.active_document);
+                }
+                if ui.button("Save As...").on_hover_text(get_config().key_bindings.text(Action::SaveAs)).clicked() {
+                    self.save_document_as(self.active_document);
                 }
             });
 
-            // Textures
-            ui.add_space(text_height * 2.);
-            ui.label("Textures:");
-            ScrollArea::vertical()
-                .id_salt("texture_scroll")
-                .max_width(usable_width)
-                .max_height(75.)
-                .show(ui, |ui| {
-                    ui.add(
-                        TextEdit::multiline(&mut self.textures_text)
-                            .code_editor()
-                            .desired_width(f32::INFINITY)
-                            .font(egui::TextStyle::Monospace)
-                            .desired_rows(10),
-                    );
-                });
-                ui.add_space(text_height);
-                if ui.button("Add Texture").clicked() {
-                    if !self.textures_text.is_empty() {
-                        self.textures_text += "\n";
-                    }
-                    self.textures_text += "temp_texture = \"linear\"";
+            // Compile material button
+            ui.horizontal(|ui| {
+                let compile_button = ui.button("Compile").on_hover_text(get_config().key_bindings.text(Action::Compile));
+                if compile_button.clicked() {
+                    self.queue_compile();
                 }
 
-            // World Offset
-            ui.add_space(text_height * 2.);
-            ui.label("World Offset");
-            ScrollArea::vertical()
-                .id_salt("world_offset")
-                .max_width(usable_width)
-                .max_height(usable_height / 5.)
-                .show(ui, |ui| {
-                    ui.add(
-                        TextEdit::multiline(&mut self.world_offset_text)
-                            .code_editor()
-                            .desired_width(f32::INFINITY)
-                            .desired_rows(25)
-                            .font(egui::TextStyle::Monospace),
-                    );
-                });
+                ui.checkbox(&mut self.live_mode, "Live")
+                    .on_hover_text("Compile automatically when edits settle and the material is valid");
 
-            // Fragment Color
-            ui.add_space(text_height * 2.);
-            ui.label("Fragment Color");
-            ScrollArea::vertical()
-                .id_salt("fragment_color")
-                .max_width(usable_width)
-                .max_height(usable_height / 5.)
-                .show(ui, |ui| {
-                    ui.add(
-                        TextEdit::multiline(&mut self.frag_color_text)
-                            .code_editor()
-                            .desired_width(f32::INFINITY)
-                            .desired_rows(15)
-                            .font(egui::TextStyle::Monospace),
-                    );
+                if ui
+                    .checkbox(&mut self.keep_last_good, "Keep last good")
+                    .on_hover_text("Keep showing the last successfully compiled material when a compile fails")
+                    .changed()
+                {
+                    let fallback_policy = if self.keep_last_good {
+                        "keep_last_good"
+                    } else {
+                        "show_broken"
+                    };
+                    self.pending_commands.push_back(format!(
+                        "set_fallback_policy##DELIM##{fallback_policy}##DELIM##"
+                    ));
+                }
+
+                ui.separator();
+                if ui
+                    .button("Find")
+                    .on_hover_text(get_config().key_bindings.text(Action::FindReplace))
+                    .clicked()
+                {
+                    self.find_replace.open(None);
+                }
+                if ui
+                    .button("Commands")
+                    .on_hover_text(get_config().key_bindings.text(Action::CommandPalette))
+                    .clicked()
+                {
+                    self.command_palette.open();
+                }
+            });
+        });
+
+        // Declared names are highlighted wherever the snippets refer to them
+        let symbols = Symbols::new(&self.document().uniforms_text, &self.document().textures_text);
+
+        // Dockable panels, sizes and docks are kept in the user settings
+        let panel_layout = get_config().panel_layout.clone();
+        for dock in [DockArea::Left, DockArea::Right, DockArea::Bottom] {
+            let id = panel_layout.panel_id(("dock", dock));
+            let saved_size = panel_layout.dock_size(dock);
+            let is_shown = !panel_layout.panels_in(dock).is_empty();
+            let add_dock = |ui: &mut egui::Ui| {
+                layout::show_dock(ui, &panel_layout, dock, &mut layout_changes, |ui, panel, part| {
+                    self.show_panel(ui, panel, part, &symbols, &mut cmd_string, &mut save_config);
                 });
+            };
 
-            // Compile material button
-            ui.add_space(text_height);
-            let compile_button = ui.button("Compile");
-            if compile_button.clicked() {
-                cmd_string = format!(
-                    "compile##DELIM##{}\n##DELIM##{}\n##DELIM##{}\n##DELIM##{}\n##DELIM##",
-                    self.uniforms_text.replace("\r", "\n").trim_start().trim_end(),
-                    self.textures_text.replace("\r", "\n").trim_start().trim_end(),
-                    self.world_offset_text.replace("\r", "\n").trim_start().trim_end(),
-                    self.frag_color_text.replace("\r", "\n").trim_start().trim_end(),
-                );
+            let size = match dock {
+                DockArea::Bottom => egui::TopBottomPanel::bottom(id)
+                    .resizable(true)
+                    .default_height(saved_size)
+                    .frame(egui::Frame::NONE)
+                    .show_animated(ctx, is_shown, add_dock)
+                    .map(|response| response.response.rect.height()),
+                _ => {
+                    let side_panel = if dock == DockArea::Left {
+                        egui::SidePanel::left(id)
+                    } else {
+                        egui::SidePanel::right(id)
+                    };
+                    side_panel
+                        .resizable(true)
+                        .default_width(saved_size)
+                        .frame(egui::Frame::NONE)
+                        .show_animated(ctx, is_shown, add_dock)
+                        .map(|response| response.response.rect.width())
+                }
+            };
+            if let Some(size) = size.filter(|size| (size - saved_size).abs() > layout::RESIZE_TOLERANCE) {
+                layout_changes.push(LayoutChange::ResizeDock(dock, size));
             }
+        }
+        CentralPanel::default().frame(egui::Frame::NONE).show(ctx, |ui| {
+            layout::show_dock(ui, &panel_layout, DockArea::Center, &mut layout_changes, |ui, panel, part| {
+                self.show_panel(ui, panel, part, &symbols, &mut cmd_string, &mut save_config);
+            });
+        });
 
-            // Texture picking + material parameter widgets
-            ui.add_space(text_height * 2.);
-            ui.horizontal(|ui| {
-                ui.label("Textures");
-                ComboBox::from_id_salt("Texture_Picker").show_ui(ui, |ui| {
-                    for i in 0..16 {
-                        let file_button = ui.button(format!("Texture[{i}]"));
-                        if file_button.clicked() {
-                            save_config = true;
-                            let file_picker = rfd::FileDialog::new().set_directory(
-                                &get_config().texture_directories[i].canonicalize().unwrap_or("./".into())
-                            );
-                            if let Some(file_path) = file_picker.pick_file() {
-                                cmd_string =
-                                    format!("load_texture##DELIM##{}##DELIM##", file_path.to_str().unwrap());
-                                if let Some(file_name) = file_path.file_name() {
-                                    self.textures[i] =
-                                        file_name.to_string_lossy().to_owned().to_string();
-                                }
-                                get_config().texture_directories[i] = file_path
-                                    .parent()
-                                    .unwrap_or(PathBuf::from("./").as_path())
-                                    .to_path_buf();
-                            }
-                        }
-                        ui.text_edit_singleline(&mut self.textures[i]);
-                    }
-                });
+        if !layout_changes.is_empty() {
+            for layout_change in layout_changes {
+                get_config().panel_layout.apply(layout_change);
+            }
+            self.layout_changed = true;
+        }
+        // Saved once the drag ends rather than on every frame of a resize
+        if self.layout_changed && !ctx.input(|input| input.pointer.any_down()) {
+            self.layout_changed = false;
+            save_config = true;
+        }
+
+        self.update_live_compile();
+        self.poll_material_file();
 
-                // Extracts variable data from [uniform_types].
-                // ex: given temp_vec4_var = { type = "vec4f", default = [1.0, 1.0, 1.0, 1.0] },
-                //  captures `temp_vec4_var` into `var_name`, and `[1.0, 1.0, 1.0, 1.0]` into `color_vec`
-                let re = Regex::new(r#"(\w+)\s*=\s*\{\s*type\s*=\s*"vec4f".*?default\s*=\s*\[(.*?)\]"#).unwrap();
+        // Texture picking and inspection windows
+        if let Some(texture_browser) = &mut self.texture_browser {
+            match texture_browser.show(ctx, &mut self.texture_infos) {
+                TextureBrowserAction::None => {}
+                TextureBrowserAction::Picked(file_path) => {
+                    let (slot, binding_name) = (texture_browser.slot, texture_browser.binding_name.clone());
+                    self.pick_texture(slot, &binding_name, file_path);
+                    self.texture_browser = None;
+                    save_config = true;
+                }
+                TextureBrowserAction::BrowseNative => {
+                    let (slot, binding_name) = (texture_browser.slot, texture_browser.binding_name.clone());
+                    let file_picker = rfd::FileDialog::new()
+                        .set_directory(&texture_browser.directory)
+                        .add_filter("Textures", &["png"]);
+                    if let Some(file_path) = file_picker.pick_file() {
+                        self.pick_texture(slot, &binding_name, file_path);
+                        self.texture_browser = None;
+                        save_config = true;
+                    }
+                }
+                TextureBrowserAction::Closed => self.texture_browser = None,
+            }
+        }
 
-                self.uniforms_text = re.replace_all(&self.uniforms_text, |caps: &regex::Captures| {
-                    let default_value = caps.get(2).map(|m| m.as_str()).unwrap_or("1., 1., 1., 1.");
-                    let mut color: [f32;4] = {
-                        let color_vec: Vec<f32>  = default_value
-                            .split(',')
-                            .map(|s| s.trim().parse::<f32>().unwrap_or(1.))
-                            .collect();
+        self.show_outside_asset_prompt(ctx);
+        self.show_compile_error_overlay(ctx);
+        self.show_disk_conflict_prompt(ctx);
+        self.show_close_prompt(ctx);
+        if let HistoryAction::Restore(sections) = self.compile_history.show_diff(ctx, &self.document().sections()) {
+            self.restore_sections(sections);
+        }
 
-                        color_vec.try_into().unwrap_or([1., 1., 1., 1.])
-                    };
+        if let Some(channel_viewer) = &mut self.channel_viewer {
+            if !channel_viewer.show(ctx) {
+                self.channel_viewer = None;
+            }
+        }
 
-                    let var_name = &caps[1];
-                    ui.label(format!("{}:", var_name));
+        save_config |= self.shortcut_editor.show(ctx, &mut get_config().key_bindings);
+        let document = &mut self.documents[self.active_document];
+        if let Some(status_text) = self.find_replace.show(ctx, document) {
+            self.status_text = status_text;
+        }
+        if let Some(status_text) = self.rename_symbol.show(ctx, document) {
+            self.status_text = status_text;
+        }
+        if let Some(action) = self.command_palette.show(ctx, &get_config().key_bindings) {
+            self.run_action(ctx, action, &mut save_config);
+        }
 
-                    let prev_color = color;
-                    let _color_picker = ui.color_edit_button_rgba_unmultiplied(&mut color);
-                    let color_str = format!("[{:.1}, {:.1}, {:.1}, {:.1}]", color[0], color[1], color[2], color[3]);
-                    if prev_color != color && cmd_string.is_empty() {
-                        cmd_string = format!(
-                            "update_uniform##DELIM##{}##DELIM##{color_str}##DELIM##", var_name
-                        );
-                    }
+        // Every change made this frame becomes, or extends, an undo step
+        let document = self.document_mut();
+        document.undo_history.observe(document.snapshot());
+        self.update_window_title(ctx);
 
-                    // Update the uniform in this match with the update color value
-                    format!(r#"{} = {{ type = "vec4f", default = {color_str}"#, var_name)
-                }).to_string();
-            });
-        });
+        if !cmd_string.is_empty() {
+            self.pending_commands.push_back(cmd_string);
+        }
 
         // Process incoming messages
+        let mut incoming_messages = Vec::new();
         if let Ok(mut shared_mem) = SHARED_MEM_FILE.try_lock() {
             let read_barrier = unsafe { &*(shared_mem.as_ptr() as *mut AtomicBool) };
 
@@ -392,12 +1704,11 @@ impl eframe::App for MaterialEditor {
                     std::str::from_utf8(&shared_mem[1..]).expect("Invalid UTF-8");
 
                 if incoming_message.as_bytes()[0] != b'\0' {
-                    println!("Gui - clear!");
-                    // Process incoming messages here
+                    incoming_messages.push(incoming_message.trim_end_matches('\0').to_string());
                     shared_mem[1..].fill(b'\0');
                 }
 
-                if cmd_string.len() > 0 {
+                if let Some(cmd_string) = self.pending_commands.pop_front() {
                     shared_mem[1..cmd_string.len() + 1].copy_from_slice(cmd_string.as_bytes());
                 }
 
@@ -407,12 +1718,20 @@ impl eframe::App for MaterialEditor {
             shared_mem.flush().expect("Failed to flush");
         }
 
+        for incoming_message in incoming_messages {
+            self.handle_message(ctx, &incoming_message);
+        }
+
+        // Keep exchanging until every queued command has been sent, then poll for engine messages
+        if !self.pending_commands.is_empty() {
+            ctx.request_repaint();
+        } else {
+            ctx.request_repaint_after(MESSAGE_POLL_INTERVAL);
+        }
+
         // Save config settings if updated
         if save_config {
-            let _ = fs::write(
-                USER_SETTINGS_PATH,
-                serde_json::to_string_pretty(&get_config()).unwrap(),
-            );
+            get_config().save(Path::new(USER_SETTINGS_PATH));
         }
     }
 
@@ -425,32 +1744,22 @@ fn main() -> eframe::Result {
     env_logger::init();
 
     // Config file
-    if !Path::new(USER_SETTINGS_PATH).exists() {
-        let default_config = UserSettings {
-            version: MATERIAL_EDITOR_VERSION,
-            shader_directory: "./".into(),
-            texture_directories: std::array::from_fn(|_| "./".into()),
-        };
-
-        let _ = fs::write(
-            USER_SETTINGS_PATH,
-            serde_json::to_string_pretty(&default_config).unwrap(),
-        );
+    let user_settings = UserSettings::load(Path::new(USER_SETTINGS_PATH));
+    for binding in user_settings.key_bindings.invalid_bindings() {
+        println!("Unknown keyboard shortcut {binding} in the settings, its action is left unbound");
     }
-
-    let settings = fs::read_to_string(USER_SETTINGS_PATH).expect("Failed to read config file");
-    let user_settings: UserSettings =
-        serde_json::from_str(&settings).expect("Failed to parse config file");
+    let window = user_settings.window;
 
     unsafe {
         GLOBAL_CONFIG = Some(user_settings);
     }
 
-    // Window and Gui
+    // Window and Gui, placed where it was last closed
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
-            .with_inner_size([640.0, 900.0])
-            .with_position([800.0, 25.0]),
+            .with_inner_size(window.size)
+            .with_position(window.position)
+            .with_maximized(window.maximized),
         ..Default::default()
     };
 
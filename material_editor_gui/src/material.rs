@@ -0,0 +1,764 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::blend::BlendState;
+
+static SCALAR_TYPES: [&str; 4] = ["f32", "i32", "u32", "bool"];
+static VECTOR_TYPE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(vec[234]|mat[234]x[234])(f|i|u|<(f32|i32|u32)>)$").unwrap());
+static ARRAY_TYPE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^array<\s*(.+?)\s*,\s*\d+\s*>$").unwrap());
+static IDENTIFIER: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[A-Za-z_][A-Za-z0-9_]*$").unwrap());
+static MAX_MATERIAL_FILE_SIZE: u64 = 1024 * 1024;
+
+/// A `vec4f` uniform declared with a default, capturing its name and the default's components.
+/// ex: `temp_vec4_var = { type = "vec4f", default = [1.0, 1.0, 1.0, 1.0] }`
+pub static VEC4_UNIFORM: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(\w+)\s*=\s*\{\s*type\s*=\s*"vec4f".*?default\s*=\s*\[(.*?)\]"#).unwrap()
+});
+
+/// The four editable sections of a material toml, and how it blends.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct MaterialSections {
+    pub uniforms: String,
+    pub textures: String,
+    pub world_offset: String,
+    pub frag_color: String,
+    // Missing from history saved before blending was configurable
+    #[serde(default)]
+    pub blend: BlendState,
+}
+
+impl MaterialSections {
+    /// Extracts the sections from a material toml, sections it doesn't contain are left empty.
+    /// An invalid `[blend_state]` is replaced with alpha blending, the error says what was wrong.
+    pub fn from_toml(material_toml: &str) -> (Self, Option<String>) {
+        let mut sections = MaterialSections::default();
+        // Files saved on Windows would otherwise never compare equal to the editor's text
+        let material_toml = &normalize_line_endings(material_toml);
+
+        let key = "[uniform_types]";
+        if let Some(snippet_key_idx) = material_toml.find(key) {
+            let snippet_start = snippet_key_idx + key.len();
+            let snippet = &material_toml[snippet_start..];
+            let snippet_end = [
+                "[texture_descs]",
+                "[get_world_offset]",
+                "[get_fragment_color]",
+                "[blend_state]",
+            ]
+            .iter()
+            .filter_map(|key| snippet.find(key))
+            .min()
+            .unwrap_or(snippet.len());
+
+            sections.uniforms = snippet[..snippet_end].trim_start().trim_end().to_string();
+        }
+
+        let key = "[texture_descs]";
+        if let Some(snippet_key_idx) = material_toml.find(key) {
+            let snippet_start = snippet_key_idx + key.len();
+            let snippet = &material_toml[snippet_start..];
+            let snippet_end = snippet.find('[').unwrap_or(snippet.len());
+
+            sections.textures = snippet[..snippet_end].trim_start().trim_end().to_string();
+        }
+
+        sections.world_offset = multiline_string_value(material_toml, "get_world_offset");
+        sections.frag_color = multiline_string_value(material_toml, "get_fragment_color");
+
+        let blend_error = match parse_blend_state(material_toml) {
+            Ok(blend) => {
+                sections.blend = blend;
+                None
+            }
+            Err(error) => Some(error),
+        };
+
+        (sections, blend_error)
+    }
+
+    pub fn to_toml(&self) -> String {
+        // Materials using the default alpha blending are saved without a [blend_state] section
+        let blend_state = if self.blend != BlendState::default() {
+            format!("\n[blend_state]\n{}\n", self.blend.to_toml())
+        } else {
+            String::new()
+        };
+
+        format!(
+            "get_world_offset = \"\"\"\n{}\n\"\"\"\n\nget_fragment_color = \"\"\"\n{}\"\"\"\n\n[uniform_types]\n{}\n\n[texture_descs]\n{}\n{blend_state}",
+            self.world_offset.trim_start().trim_end(),
+            self.frag_color.trim_start().trim_end(),
+            self.uniforms.trim_start().trim_end(),
+            self.textures.trim_start().trim_end(),
+        )
+        .replace("\r", "\n")
+    }
+
+    /// The sections as they read back after a save, for comparing against what is on disk.
+    pub fn normalized(&self) -> Self {
+        let normalize = |text: &str| normalize_line_endings(text).trim().to_string();
+        Self {
+            uniforms: normalize(&self.uniforms),
+            textures: normalize(&self.textures),
+            world_offset: normalize(&self.world_offset),
+            frag_color: normalize(&self.frag_color),
+            blend: self.blend,
+        }
+    }
+
+    /// Checks the sections are well formed before sending them to the engine.
+    /// Returns one message per problem found, prefixed with the section it is in.
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        errors.extend(
+            validate_uniform_types(&self.uniforms)
+                .into_iter()
+                .map(|error| format!("Uniforms: {error}")),
+        );
+        errors.extend(
+            validate_texture_descs(&self.textures)
+                .into_iter()
+                .map(|error| format!("Textures: {error}")),
+        );
+        for (section, snippet) in [
+            ("World Offset", &self.world_offset),
+            ("Fragment Color", &self.frag_color),
+        ] {
+            if let Err(error) = validate_snippet(snippet) {
+                errors.push(format!("{section}: {error}"));
+            }
+        }
+
+        errors
+    }
+}
+
+fn normalize_line_endings(text: &str) -> String {
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+/// Name and default of every `vec4f` uniform declared with one, as the engine's update_uniform takes them.
+/// ex: `tint = { type = "vec4f", default = [1.0, 0.5, 0.5, 1.0] }` gives `("tint", "1.0, 0.5, 0.5, 1.0")`
+pub fn vec4_uniform_defaults(uniforms_text: &str) -> Vec<(String, String)> {
+    VEC4_UNIFORM
+        .captures_iter(uniforms_text)
+        .map(|captures| (captures[1].to_string(), captures[2].trim().to_string()))
+        .collect()
+}
+
+/// The uniforms with every vec4 default left out, texts that match here only differ in values
+/// update_uniform can send without rebuilding the material.
+pub fn without_vec4_defaults(uniforms_text: &str) -> String {
+    VEC4_UNIFORM
+        .replace_all(uniforms_text, r#"$1 = { type = "vec4f", default = []"#)
+        .split_whitespace()
+        .collect()
+}
+
+/// Reads the material's `[blend_state]` section, materials without one use alpha blending.
+fn parse_blend_state(material_toml: &str) -> Result<BlendState, String> {
+    let key = "[blend_state]";
+    let Some(snippet_key_idx) = material_toml.find(key) else {
+        return Ok(BlendState::default());
+    };
+    let snippet_start = snippet_key_idx + key.len();
+    let snippet = &material_toml[snippet_start..];
+    let snippet_end = snippet.find("\n[").unwrap_or(snippet.len());
+
+    snippet[..snippet_end]
+        .parse::<toml::Table>()
+        .map_err(|error| error.message().to_string())
+        .and_then(|table| BlendState::from_toml(&table))
+}
+
+// Contents of a `key = \"\"\" ... \"\"\"` string, trimmed
+fn multiline_string_value(material_toml: &str, key: &str) -> String {
+    let Some(snippet_key_idx) = material_toml.find(key) else {
+        return String::new();
+    };
+
+    let snippet = &material_toml[snippet_key_idx..];
+    let Some(start) = snippet.find("\"\"\"") else {
+        return String::new();
+    };
+    let Some(end) = snippet[start + 3..].find("\"\"\"") else {
+        return String::new();
+    };
+
+    snippet[start + 3..start + 3 + end]
+        .trim_start()
+        .trim_end()
+        .to_string()
+}
+
+/// Whether `uniform_type` is a type the `[uniform_types]` section can declare.
+/// ex: `f32`, `vec4f`, `vec3<f32>`, `mat4x4f`, `array<vec4f, 3>`
+pub fn is_valid_uniform_type(uniform_type: &str) -> bool {
+    let uniform_type = uniform_type.trim();
+    if SCALAR_TYPES.contains(&uniform_type) || VECTOR_TYPE.is_match(uniform_type) {
+        return true;
+    }
+
+    ARRAY_TYPE
+        .captures(uniform_type)
+        .is_some_and(|captures| is_valid_uniform_type(&captures[1]))
+}
+
+/// Checks every `[uniform_types]` entry is either `name = "type"` or `name = { type = "type", default = ... }`.
+pub fn validate_uniform_types(uniforms_text: &str) -> Vec<String> {
+    let uniforms = match uniforms_text.parse::<toml::Table>() {
+        Ok(uniforms) => uniforms,
+        Err(error) => return vec![error.message().to_string()],
+    };
+
+    uniforms
+        .iter()
+        .filter_map(|(name, value)| {
+            let uniform_type = match value {
+                toml::Value::String(uniform_type) => uniform_type,
+                toml::Value::Table(table) => match table.get("type") {
+                    Some(toml::Value::String(uniform_type)) => uniform_type,
+                    _ => return Some(format!("`{name}` is missing its `type`")),
+                },
+                _ => return Some(format!("`{name}` must be a type string or a table")),
+            };
+
+            (!is_valid_uniform_type(uniform_type))
+                .then(|| format!("`{name}` has unsupported type `{uniform_type}`"))
+        })
+        .collect()
+}
+
+/// Checks every `[texture_descs]` entry is a valid sampler description.
+pub fn validate_texture_descs(textures_text: &str) -> Vec<String> {
+    let mut errors = Vec::new();
+    if let Err(error) = textures_text.parse::<toml::Table>() {
+        errors.push(error.message().to_string());
+        return errors;
+    }
+
+    for table in textures_text
+        .lines()
+        .filter_map(|line| line.parse::<toml::Table>().ok())
+    {
+        for (name, value) in table {
+            if let Err(error) = SamplerDesc::from_toml(&value) {
+                errors.push(format!("`{name}` {error}"));
+            }
+        }
+    }
+
+    errors
+}
+
+/// Checks a WGSL snippet can be embedded in the material and has balanced brackets.
+pub fn validate_snippet(snippet: &str) -> Result<(), String> {
+    if snippet.trim().is_empty() {
+        return Err("snippet is empty".to_string());
+    }
+    if snippet.contains("\"\"\"") {
+        return Err("snippet can't contain `\"\"\"`".to_string());
+    }
+
+    let mut open_brackets = Vec::new();
+    for (line_index, line) in snippet.lines().enumerate() {
+        let code = line.split("//").next().unwrap_or_default();
+        for c in code.chars() {
+            match c {
+                '(' | '[' | '{' => open_brackets.push((c, line_index + 1)),
+                ')' | ']' | '}' => {
+                    let expected = match c {
+                        ')' => '(',
+                        ']' => '[',
+                        _ => '{',
+                    };
+                    match open_brackets.pop() {
+                        Some((open, _)) if open == expected => {}
+                        _ => return Err(format!("unmatched `{c}` on line {}", line_index + 1)),
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    match open_brackets.pop() {
+        Some((open, line)) => Err(format!("unclosed `{open}` from line {line}")),
+        None => Ok(()),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum FilterMode {
+    Nearest,
+    #[default]
+    Linear,
+}
+
+impl FilterMode {
+    pub const ALL: [FilterMode; 2] = [FilterMode::Nearest, FilterMode::Linear];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FilterMode::Nearest => "nearest",
+            FilterMode::Linear => "linear",
+        }
+    }
+}
+
+/// How a texture binding is sampled.
+///
+/// The engine reads nothing but a filter per binding, ex: `color_tex = "nearest"`, so that is
+/// all the editor reads and writes.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SamplerDesc {
+    pub filter: FilterMode,
+}
+
+impl SamplerDesc {
+    pub fn from_toml(value: &toml::Value) -> Result<Self, String> {
+        match value {
+            toml::Value::String(filter) => Ok(Self {
+                filter: parse_filter(filter)?,
+            }),
+            toml::Value::Table(_) => Err(
+                "sampler tables aren't supported by the engine, use a filter string".to_string(),
+            ),
+            _ => Err("expected a filter string, nearest or linear".to_string()),
+        }
+    }
+
+    /// `[texture_descs]` value describing this sampler.
+    pub fn to_toml(self) -> String {
+        format!("\"{}\"", self.filter.name())
+    }
+}
+
+fn parse_filter(filter: &str) -> Result<FilterMode, String> {
+    FilterMode::ALL
+        .into_iter()
+        .find(|mode| mode.name() == filter)
+        .ok_or(format!(
+            "unknown filter `{filter}`, expected nearest or linear"
+        ))
+}
+
+/// A texture binding declared in a material's `[texture_descs]` section.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextureBinding {
+    pub name: String,
+    // Invalid samplers are kept as errors so editing never overwrites what was written
+    pub sampler: Result<SamplerDesc, String>,
+}
+
+/// Parses the `[texture_descs]` section into its bindings, in declaration order.
+///
+/// The binding's index in the returned list is the texture slot the engine binds it to.
+pub fn parse_texture_bindings(textures_text: &str) -> Vec<TextureBinding> {
+    textures_text
+        .lines()
+        .filter_map(|line| line.parse::<toml::Table>().ok())
+        .flat_map(|table| table.into_iter())
+        .map(|(name, value)| TextureBinding {
+            name,
+            sampler: SamplerDesc::from_toml(&value),
+        })
+        .collect()
+}
+
+/// Rewrites the declaration of `binding_name` in the `[texture_descs]` section with `sampler`.
+pub fn set_texture_binding_sampler(
+    textures_text: &str,
+    binding_name: &str,
+    sampler: &SamplerDesc,
+) -> String {
+    textures_text
+        .lines()
+        .map(|line| {
+            let declares_binding = line
+                .parse::<toml::Table>()
+                .is_ok_and(|table| table.contains_key(binding_name));
+            if declares_binding {
+                format!("{binding_name} = {}", sampler.to_toml())
+            } else {
+                line.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// First `temp_texture` style name not already declared in `[texture_descs]`.
+pub fn unused_texture_binding_name(textures_text: &str) -> String {
+    let bindings = parse_texture_bindings(textures_text);
+    (0..)
+        .map(|index| match index {
+            0 => "temp_texture".to_string(),
+            _ => format!("temp_texture_{index}"),
+        })
+        .find(|name| !bindings.iter().any(|binding| &binding.name == name))
+        .unwrap_or_default()
+}
+
+/// Renames the uniform or texture binding `old_name` in its declaration and in the snippets.
+/// Only `scene_instance.<name>` accesses and arguments of texture builtins are rewritten, so
+/// locals sharing the name are left alone. Renaming a texture also renames `sampler_<name>`.
+pub fn rename_symbol(
+    sections: &mut MaterialSections,
+    old_name: &str,
+    new_name: &str,
+) -> Result<(), String> {
+    if !IDENTIFIER.is_match(new_name) {
+        return Err(format!("`{new_name}` is not a valid name"));
+    }
+
+    let uniform_names: Vec<String> = sections
+        .uniforms
+        .parse::<toml::Table>()
+        .map(|uniforms| uniforms.keys().cloned().collect())
+        .unwrap_or_default();
+    let texture_names: Vec<String> = parse_texture_bindings(&sections.textures)
+        .into_iter()
+        .map(|binding| binding.name)
+        .collect();
+    let is_declared = |name: &str| {
+        uniform_names
+            .iter()
+            .chain(&texture_names)
+            .any(|declared_name| declared_name == name)
+    };
+    if !is_declared(old_name) {
+        return Err(format!("`{old_name}` is not a declared uniform or texture"));
+    }
+    if is_declared(new_name) {
+        return Err(format!("`{new_name}` is already declared"));
+    }
+
+    // Declarations are the `name = ...` keys at the start of a line
+    let declaration = Regex::new(&format!(r"(?m)^(\s*){}(\s*=)", regex::escape(old_name))).unwrap();
+    let declaration_replacement = format!("${{1}}{new_name}${{2}}");
+    let is_texture = texture_names
+        .iter()
+        .any(|texture_name| texture_name == old_name);
+    let declarations = if is_texture {
+        &mut sections.textures
+    } else {
+        &mut sections.uniforms
+    };
+    *declarations = declaration
+        .replace_all(declarations, declaration_replacement.as_str())
+        .to_string();
+
+    for snippet in [&mut sections.world_offset, &mut sections.frag_color] {
+        *snippet = if is_texture {
+            rename_texture_arguments(snippet, old_name, new_name)
+        } else {
+            rename_uniform_accesses(snippet, old_name, new_name)
+        };
+    }
+
+    Ok(())
+}
+
+// Uniforms are only reachable as `scene_instance.<name>`, a bare `<name>` is a local or a builtin
+fn rename_uniform_accesses(snippet: &str, old_name: &str, new_name: &str) -> String {
+    let access = Regex::new(&format!(
+        r"(\bscene_instance\s*\.\s*){}\b",
+        regex::escape(old_name)
+    ))
+    .unwrap();
+    access
+        .replace_all(snippet, format!("${{1}}{new_name}"))
+        .to_string()
+}
+
+// Textures and their samplers are only used as whole arguments of the `texture*` builtins,
+// ex: `textureSample(color_tex, sampler_color_tex, uv)`
+fn rename_texture_arguments(snippet: &str, old_name: &str, new_name: &str) -> String {
+    static TEXTURE_CALL: Lazy<Regex> = Lazy::new(|| Regex::new(r"\btexture\w*\s*\(").unwrap());
+    let renames = [
+        (old_name.to_string(), new_name.to_string()),
+        (format!("sampler_{old_name}"), format!("sampler_{new_name}")),
+    ];
+
+    // Byte ranges of the renamed arguments, nested calls are matched on their own
+    let mut replacements = Vec::new();
+    for call in TEXTURE_CALL.find_iter(snippet) {
+        let mut depth = 0;
+        let mut argument_start = call.end();
+        for (offset, character) in snippet[call.end()..].char_indices() {
+            let position = call.end() + offset;
+            match character {
+                '(' | '[' => depth += 1,
+                ')' | ']' if depth > 0 => depth -= 1,
+                ',' | ')' if depth == 0 => {
+                    let argument = &snippet[argument_start..position];
+                    let trimmed = argument.trim();
+                    if let Some((_, new_reference)) = renames
+                        .iter()
+                        .find(|(old_reference, _)| trimmed == old_reference)
+                    {
+                        let start = argument_start + argument.len() - argument.trim_start().len();
+                        replacements.push((start..start + trimmed.len(), new_reference));
+                    }
+                    if character == ')' {
+                        break;
+                    }
+                    argument_start = position + 1;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let mut renamed = snippet.to_string();
+    replacements.sort_by_key(|(range, _)| range.start);
+    for (range, new_reference) in replacements.into_iter().rev() {
+        renamed.replace_range(range, new_reference);
+    }
+    renamed
+}
+
+/// Every material .toml file under `directory`, sorted by path. Hidden directories and `target/`
+/// are skipped.
+pub fn find_material_files(directory: &Path) -> Vec<PathBuf> {
+    let mut materials = Vec::new();
+    collect_material_files(directory, &mut materials);
+    materials.sort();
+    materials
+}
+
+fn collect_material_files(directory: &Path, materials: &mut Vec<PathBuf>) {
+    let Ok(dir_entries) = fs::read_dir(directory) else {
+        return;
+    };
+
+    for path in dir_entries.filter_map(|dir_entry| Some(dir_entry.ok()?.path())) {
+        if path.is_dir() {
+            if !is_skipped_directory(&path) {
+                collect_material_files(&path, materials);
+            }
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == "toml")
+            && is_material_toml(&path)
+        {
+            materials.push(path);
+        }
+    }
+}
+
+/// Hidden directories and build output hold no materials, only lots of files to walk.
+pub fn is_skipped_directory(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with('.') || name == "target")
+}
+
+// Cargo manifests, settings and other tomls have no fragment shader.
+// Materials are a few KB, larger tomls are something else and aren't worth reading
+fn is_material_toml(path: &Path) -> bool {
+    fs::metadata(path).is_ok_and(|metadata| metadata.len() <= MAX_MATERIAL_FILE_SIZE)
+        && fs::read_to_string(path).is_ok_and(|toml| toml.contains("get_fragment_color"))
+}
+
+/// Path of the sidecar file holding the textures assigned to each binding of `material_path`.
+/// ex: `shaders/default_sprite.toml` -> `shaders/default_sprite.textures.json`
+pub fn texture_sidecar_path(material_path: &Path) -> PathBuf {
+    material_path.with_extension("textures.json")
+}
+
+/// Reads the binding name -> texture path assignments saved next to `material_path`.
+pub fn load_texture_assignments(material_path: &Path) -> BTreeMap<String, PathBuf> {
+    fs::read_to_string(texture_sidecar_path(material_path))
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_texture_assignments(material_path: &Path, assignments: &BTreeMap<String, PathBuf>) {
+    let sidecar_path = texture_sidecar_path(material_path);
+    if assignments.is_empty() {
+        let _ = fs::remove_file(&sidecar_path);
+        return;
+    }
+
+    match serde_json::to_string_pretty(assignments) {
+        Ok(json) => {
+            if let Err(error) = fs::write(&sidecar_path, json) {
+                println!(
+                    "Failed to write texture assignments {} with error {error}",
+                    sidecar_path.to_string_lossy()
+                );
+            }
+        }
+        Err(error) => println!("Failed to serialize texture assignments with error {error}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn texture_bindings_keep_declaration_order() {
+        let bindings = parse_texture_bindings(
+            "color_tex = \"linear\"\n\nnormal_tex = \"nearest\"\nmask = \"linear\"",
+        );
+
+        let names: Vec<&str> = bindings
+            .iter()
+            .map(|binding| binding.name.as_str())
+            .collect();
+        assert_eq!(names, ["color_tex", "normal_tex", "mask"]);
+    }
+
+    #[test]
+    fn texture_bindings_skip_lines_that_are_not_declarations() {
+        let bindings = parse_texture_bindings("# comment\ncolor_tex = \"linear\"\nnot toml\n");
+
+        assert_eq!(bindings.len(), 1);
+        assert_eq!(bindings[0].name, "color_tex");
+        assert!(parse_texture_bindings("").is_empty());
+    }
+
+    #[test]
+    fn vec4_defaults_are_all_update_uniform_can_send() {
+        let uniforms = "tint = { type = \"vec4f\", default = [1.0, 0.5, 0.5, 1.0] }\nstrength = { type = \"f32\", default = 0.5 }";
+        assert_eq!(
+            vec4_uniform_defaults(uniforms),
+            [("tint".to_string(), "1.0, 0.5, 0.5, 1.0".to_string())]
+        );
+
+        let new_tint = uniforms.replace("0.5, 0.5", "0.2,  0.3");
+        assert_eq!(
+            without_vec4_defaults(uniforms),
+            without_vec4_defaults(&new_tint)
+        );
+
+        let new_strength = uniforms.replace("default = 0.5", "default = 2.0");
+        assert_ne!(
+            without_vec4_defaults(uniforms),
+            without_vec4_defaults(&new_strength)
+        );
+        let new_type = uniforms.replace("\"f32\"", "\"vec4f\"");
+        assert_ne!(
+            without_vec4_defaults(uniforms),
+            without_vec4_defaults(&new_type)
+        );
+    }
+
+    #[test]
+    fn line_endings_become_newlines() {
+        assert_eq!(normalize_line_endings("a\r\nb\rc\nd"), "a\nb\nc\nd");
+        assert_eq!(normalize_line_endings("\r\n\r\n"), "\n\n");
+    }
+
+    #[test]
+    fn crlf_material_reads_like_the_lf_one() {
+        let material_toml = "get_world_offset = \"\"\"\nreturn vec2f(0., 0.);\n\"\"\"\n\nget_fragment_color = \"\"\"\nlet color = vec4f(1.);\nreturn color;\"\"\"\n\n[uniform_types]\ntint = \"vec4f\"\n\n[texture_descs]\ncolor_tex = \"linear\"\n";
+        let (lf_sections, _) = MaterialSections::from_toml(material_toml);
+        let (crlf_sections, _) = MaterialSections::from_toml(&material_toml.replace('\n', "\r\n"));
+
+        assert_eq!(crlf_sections, lf_sections);
+        assert_eq!(
+            crlf_sections.frag_color,
+            "let color = vec4f(1.);\nreturn color;"
+        );
+        assert!(!crlf_sections.to_toml().contains('\r'));
+    }
+
+    #[test]
+    fn normalized_sections_drop_carriage_returns() {
+        let sections = MaterialSections {
+            uniforms: "tint = \"vec4f\"\r\n".to_string(),
+            frag_color: "\r\nlet a = 1.;\r\nreturn vec4f(a);\r\n".to_string(),
+            ..Default::default()
+        };
+
+        let normalized = sections.normalized();
+        assert_eq!(normalized.uniforms, "tint = \"vec4f\"");
+        assert_eq!(normalized.frag_color, "let a = 1.;\nreturn vec4f(a);");
+    }
+
+    #[test]
+    fn sampler_reads_filter_strings() {
+        for (filter, expected) in [
+            ("nearest", FilterMode::Nearest),
+            ("linear", FilterMode::Linear),
+        ] {
+            let sampler = SamplerDesc::from_toml(&toml::Value::String(filter.to_string()));
+            assert_eq!(sampler, Ok(SamplerDesc { filter: expected }));
+            assert_eq!(sampler.unwrap().to_toml(), format!("\"{filter}\""));
+        }
+    }
+
+    #[test]
+    fn sampler_rejects_tables_the_engine_cant_read() {
+        let table: toml::Table = "color_tex = { filter = \"linear\", address_mode = \"repeat\" }"
+            .parse()
+            .unwrap();
+
+        assert!(SamplerDesc::from_toml(&table["color_tex"]).is_err());
+        assert_eq!(
+            validate_texture_descs("color_tex = { filter = \"linear\" }").len(),
+            1
+        );
+    }
+
+    #[test]
+    fn sampler_rejects_invalid_values() {
+        assert_eq!(
+            SamplerDesc::from_toml(&toml::Value::String("cubic".to_string())),
+            Err("unknown filter `cubic`, expected nearest or linear".to_string())
+        );
+        assert!(SamplerDesc::from_toml(&toml::Value::Integer(1)).is_err());
+        assert!(validate_texture_descs("color_tex = \"linear\"\nmask = \"nearest\"").is_empty());
+    }
+
+    #[test]
+    fn rename_uniform_keeps_locals_with_the_same_name() {
+        let mut sections = MaterialSections {
+            uniforms: "tint = { type = \"vec4f\", default = [1.0, 1.0, 1.0, 1.0] }".to_string(),
+            frag_color:
+                "let tint = scene_instance.tint * 2.0;\nreturn tint + scene_instance . tint;"
+                    .to_string(),
+            ..Default::default()
+        };
+
+        rename_symbol(&mut sections, "tint", "color").unwrap();
+
+        assert!(sections.uniforms.starts_with("color = "));
+        assert_eq!(
+            sections.frag_color,
+            "let tint = scene_instance.color * 2.0;\nreturn tint + scene_instance . color;"
+        );
+    }
+
+    #[test]
+    fn rename_texture_only_renames_texture_arguments() {
+        let mut sections = MaterialSections {
+            textures: "color_tex = \"linear\"".to_string(),
+            frag_color: "let color_tex = 0.5;\nreturn color_tex * textureSample(color_tex, sampler_color_tex, vec2f(uv0.x, color_tex));"
+                .to_string(),
+            ..Default::default()
+        };
+
+        rename_symbol(&mut sections, "color_tex", "albedo").unwrap();
+
+        assert_eq!(sections.textures, "albedo = \"linear\"");
+        assert_eq!(
+            sections.frag_color,
+            "let color_tex = 0.5;\nreturn color_tex * textureSample(albedo, sampler_albedo, vec2f(uv0.x, color_tex));"
+        );
+    }
+}
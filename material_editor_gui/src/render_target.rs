@@ -0,0 +1,189 @@
+use eframe::egui::{self, ComboBox};
+
+/// Render target the preview sprites are drawn into, compiled pipelines built for it show in the preview.
+pub static SCENE_RENDER_TARGET: &str = "scene";
+
+/// A render target reported by the engine that material pipelines can be built for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderTargetInfo {
+    pub name: String,
+    pub format: String,
+    pub sample_count: u32,
+    pub supported_sample_counts: Vec<u32>,
+}
+
+/// Parses the `name \t format \t sample count \t supported sample counts` lines of a `render_targets` message.
+pub fn parse_render_targets(message: &str) -> Vec<RenderTargetInfo> {
+    message
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split('\t').collect();
+            let [name, format, sample_count, supported_sample_counts] = fields[..] else {
+                return None;
+            };
+
+            Some(RenderTargetInfo {
+                name: name.to_string(),
+                format: format.to_string(),
+                sample_count: sample_count.parse().ok()?,
+                supported_sample_counts: supported_sample_counts
+                    .split(',')
+                    .filter_map(|sample_count| sample_count.trim().parse().ok())
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+/// The render target and MSAA sample count compiled pipelines are built for.
+/// Unknown until the engine reports its render targets.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PipelineTarget {
+    pub render_target: String,
+    pub sample_count: u32,
+}
+
+impl PipelineTarget {
+    /// The scene pass with the sample count the engine reported for it.
+    pub fn scene(render_targets: &[RenderTargetInfo]) -> Option<Self> {
+        render_targets
+            .iter()
+            .find(|render_target| render_target.name == SCENE_RENDER_TARGET)
+            .map(|scene| Self {
+                render_target: scene.name.clone(),
+                sample_count: scene.sample_count,
+            })
+    }
+
+    pub fn is_scene(&self, render_targets: &[RenderTargetInfo]) -> bool {
+        Self::scene(render_targets).as_ref() == Some(self)
+    }
+
+    pub fn command(&self) -> String {
+        format!(
+            "set_pipeline_target##DELIM##{}##DELIM##{}##DELIM##",
+            self.render_target, self.sample_count
+        )
+    }
+
+    // ex: `color (Bgra8UnormSrgb) x4`
+    pub fn label(&self, render_targets: &[RenderTargetInfo]) -> String {
+        match render_targets
+            .iter()
+            .find(|render_target| render_target.name == self.render_target)
+        {
+            Some(render_target) => format!(
+                "{} ({}) x{}",
+                render_target.name, render_target.format, self.sample_count
+            ),
+            None => format!("{} x{}", self.render_target, self.sample_count),
+        }
+    }
+
+    /// Target and MSAA pickers, returns true if the selection changed.
+    pub fn show(&mut self, ui: &mut egui::Ui, render_targets: &[RenderTargetInfo]) -> bool {
+        let previous = self.clone();
+
+        ui.label("Target");
+        ComboBox::from_id_salt("pipeline_render_target")
+            .selected_text(&self.render_target)
+            .show_ui(ui, |ui| {
+                for render_target in render_targets {
+                    let selected = self.render_target == render_target.name;
+                    if ui
+                        .selectable_label(
+                            selected,
+                            format!("{} ({})", render_target.name, render_target.format),
+                        )
+                        .clicked()
+                        && !selected
+                    {
+                        // Start from the sample count the engine itself uses for the target
+                        self.render_target = render_target.name.clone();
+                        self.sample_count = render_target.sample_count;
+                    }
+                }
+            })
+            .response
+            .on_hover_text("Render target format compiled pipelines are built for");
+
+        let supported_sample_counts = render_targets
+            .iter()
+            .find(|render_target| render_target.name == self.render_target)
+            .map(|render_target| render_target.supported_sample_counts.clone())
+            .unwrap_or_else(|| vec![self.sample_count]);
+        ui.label("MSAA");
+        ComboBox::from_id_salt("pipeline_sample_count")
+            .selected_text(format!("x{}", self.sample_count))
+            .show_ui(ui, |ui| {
+                for sample_count in supported_sample_counts {
+                    ui.selectable_value(
+                        &mut self.sample_count,
+                        sample_count,
+                        format!("x{sample_count}"),
+                    );
+                }
+            });
+
+        *self != previous
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_targets_parse_one_per_line() {
+        let render_targets = parse_render_targets(
+            "scene\tBgra8UnormSrgb\t4\t1,2,4\ncolor_resolve\tBgra8UnormSrgb\t1\t1\n",
+        );
+
+        assert_eq!(
+            render_targets,
+            [
+                RenderTargetInfo {
+                    name: "scene".to_string(),
+                    format: "Bgra8UnormSrgb".to_string(),
+                    sample_count: 4,
+                    supported_sample_counts: vec![1, 2, 4],
+                },
+                RenderTargetInfo {
+                    name: "color_resolve".to_string(),
+                    format: "Bgra8UnormSrgb".to_string(),
+                    sample_count: 1,
+                    supported_sample_counts: vec![1],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed_render_target_lines_are_skipped() {
+        let render_targets = parse_render_targets(
+            "scene\tRgba8Unorm\tfour\t1\ncolor\tRgba8Unorm\n\ndepth\tDepth32Float\t1\t1, x, 4",
+        );
+
+        assert_eq!(render_targets.len(), 1);
+        assert_eq!(render_targets[0].name, "depth");
+        assert_eq!(render_targets[0].supported_sample_counts, [1, 4]);
+    }
+
+    #[test]
+    fn scene_target_uses_the_reported_sample_count() {
+        assert_eq!(PipelineTarget::scene(&[]), None);
+
+        let render_targets =
+            parse_render_targets("color\tRgba8Unorm\t1\t1\nscene\tRgba8Unorm\t1\t1,4\n");
+        let scene = PipelineTarget::scene(&render_targets).unwrap();
+        assert_eq!(scene.sample_count, 1);
+        assert!(scene.is_scene(&render_targets));
+        assert!(
+            !PipelineTarget {
+                sample_count: 4,
+                ..scene
+            }
+            .is_scene(&render_targets)
+        );
+    }
+}
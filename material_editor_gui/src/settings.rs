@@ -0,0 +1,348 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+
+use crate::{MATERIAL_EDITOR_VERSION, MAX_TEXTURES, layout::PanelLayout, shortcuts::KeyBindings};
+
+static MAX_RECENT_FILES: usize = 10;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct WindowGeometry {
+    pub position: [f32; 2],
+    pub size: [f32; 2],
+    pub maximized: bool,
+}
+
+impl Default for WindowGeometry {
+    fn default() -> Self {
+        Self {
+            position: [800., 25.],
+            size: [640., 900.],
+            maximized: false,
+        }
+    }
+}
+
+/// Editor settings kept between sessions in `user_settings.json`.
+///
+/// Every field has a default, so files missing newer fields still load and an invalid field only
+/// resets itself. Files written by older versions are migrated up to `MATERIAL_EDITOR_VERSION` first.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default)]
+pub struct UserSettings {
+    pub version: u32,
+    pub shader_directory: PathBuf,
+    // Directory the workspace panel lists materials from, the shader directory until one is chosen
+    pub workspace_directory: Option<PathBuf>,
+    #[serde(deserialize_with = "deserialize_texture_directories")]
+    pub texture_directories: [PathBuf; MAX_TEXTURES],
+    // Most recently opened first
+    pub recent_files: Vec<PathBuf>,
+    pub window: WindowGeometry,
+    pub panel_layout: PanelLayout,
+    pub key_bindings: KeyBindings,
+    // Settings this version doesn't know, ex: written by a newer editor. Saved back untouched
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, Value>,
+}
+
+impl Default for UserSettings {
+    fn default() -> Self {
+        Self {
+            version: MATERIAL_EDITOR_VERSION,
+            shader_directory: "./".into(),
+            workspace_directory: None,
+            texture_directories: std::array::from_fn(|_| "./".into()),
+            recent_files: Vec::new(),
+            window: WindowGeometry::default(),
+            panel_layout: PanelLayout::default(),
+            key_bindings: KeyBindings::default(),
+            extra: serde_json::Map::new(),
+        }
+    }
+}
+
+// Hand edits may list more or fewer directories than there are texture slots
+fn deserialize_texture_directories<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<[PathBuf; MAX_TEXTURES], D::Error> {
+    let texture_directories = Vec::<PathBuf>::deserialize(deserializer)?;
+    Ok(std::array::from_fn(|slot| {
+        texture_directories
+            .get(slot)
+            .cloned()
+            .unwrap_or("./".into())
+    }))
+}
+
+// `SETTINGS_MIGRATIONS[n]` upgrades settings written by version n to version n + 1
+static SETTINGS_MIGRATIONS: &[fn(&mut serde_json::Map<String, Value>)] = &[migrate_from_version_0];
+
+// Recent files, window geometry and panel layout were added in version 1, defaults fill them in.
+// Version 0 also wrote the shader directory as an empty string until one was picked
+fn migrate_from_version_0(settings: &mut serde_json::Map<String, Value>) {
+    if settings
+        .get("shader_directory")
+        .is_some_and(|shader_directory| shader_directory == "")
+    {
+        settings.insert("shader_directory".to_string(), "./".into());
+    }
+}
+
+// Keeps a copy of a settings file that couldn't be read, so hand edits aren't lost to the defaults
+fn back_up(settings_path: &Path, reason: &str) {
+    let seconds = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let backup_path = settings_path.with_extension(format!("{seconds}.bak"));
+
+    match fs::copy(settings_path, &backup_path) {
+        Ok(_) => println!(
+            "{reason}. The old file was kept as {}",
+            backup_path.to_string_lossy()
+        ),
+        Err(error) => println!("{reason}. Failed to back up the old file with error {error}"),
+    }
+}
+
+impl UserSettings {
+    /// Loads the settings at `settings_path`, falling back to defaults rather than failing.
+    pub fn load(settings_path: &Path) -> Self {
+        if !settings_path.exists() {
+            let settings = Self::default();
+            settings.save(settings_path);
+            return settings;
+        }
+
+        let settings_json = match fs::read_to_string(settings_path) {
+            Ok(settings_json) => settings_json,
+            Err(error) => {
+                println!(
+                    "Failed to read settings {} with error {error}, using default settings",
+                    settings_path.to_string_lossy()
+                );
+                return Self::default();
+            }
+        };
+
+        let mut settings_value = match serde_json::from_str::<Value>(&settings_json) {
+            Ok(Value::Object(settings_value)) => settings_value,
+            Ok(_) => {
+                back_up(
+                    settings_path,
+                    "Settings are not a json object, using default settings",
+                );
+                return Self::default();
+            }
+            Err(error) => {
+                back_up(
+                    settings_path,
+                    &format!("Invalid settings json: {error}, using default settings"),
+                );
+                return Self::default();
+            }
+        };
+
+        let version = settings_value
+            .get("version")
+            .and_then(Value::as_u64)
+            .unwrap_or_default() as u32;
+        if version > MATERIAL_EDITOR_VERSION {
+            println!(
+                "Settings were written by a newer editor (version {version}), unknown settings are kept but ignored"
+            );
+        }
+        for migration in SETTINGS_MIGRATIONS
+            .iter()
+            .take(MATERIAL_EDITOR_VERSION as usize)
+            .skip(version as usize)
+        {
+            migration(&mut settings_value);
+        }
+
+        let mut settings: Self = match serde_json::from_value(Value::Object(settings_value.clone()))
+        {
+            Ok(settings) => settings,
+            Err(_) => {
+                let (settings, invalid_fields) = Self::from_valid_fields(settings_value);
+                back_up(
+                    settings_path,
+                    &format!(
+                        "Invalid settings {}, using their defaults",
+                        invalid_fields.join(", ")
+                    ),
+                );
+                settings
+            }
+        };
+
+        if version < MATERIAL_EDITOR_VERSION {
+            println!("Migrated settings from version {version} to {MATERIAL_EDITOR_VERSION}");
+            settings.version = MATERIAL_EDITOR_VERSION;
+            settings.save(settings_path);
+        }
+
+        settings
+    }
+
+    // Adds the fields one at a time on top of the defaults, leaving out the ones that don't deserialize.
+    // Returns the settings and the names of the fields left out
+    fn from_valid_fields(settings_value: serde_json::Map<String, Value>) -> (Self, Vec<String>) {
+        let mut valid_fields = match serde_json::to_value(Self::default()) {
+            Ok(Value::Object(defaults)) => defaults,
+            _ => serde_json::Map::new(),
+        };
+        let mut invalid_fields = Vec::new();
+
+        for (key, value) in settings_value {
+            let previous = valid_fields.insert(key.clone(), value);
+            if serde_json::from_value::<Self>(Value::Object(valid_fields.clone())).is_err() {
+                match previous {
+                    Some(previous) => valid_fields.insert(key.clone(), previous),
+                    None => valid_fields.remove(&key),
+                };
+                invalid_fields.push(key);
+            }
+        }
+
+        let settings = serde_json::from_value(Value::Object(valid_fields)).unwrap_or_default();
+        (settings, invalid_fields)
+    }
+
+    pub fn save(&self, settings_path: &Path) {
+        let settings_json = match serde_json::to_string_pretty(self) {
+            Ok(settings_json) => settings_json,
+            Err(error) => {
+                println!("Failed to serialize settings with error {error}");
+                return;
+            }
+        };
+
+        if let Err(error) = fs::write(settings_path, settings_json) {
+            println!(
+                "Failed to write settings {} with error {error}",
+                settings_path.to_string_lossy()
+            );
+        }
+    }
+
+    pub fn workspace_directory(&self) -> &Path {
+        self.workspace_directory
+            .as_deref()
+            .unwrap_or(&self.shader_directory)
+    }
+
+    pub fn add_recent_file(&mut self, file_path: &Path) {
+        self.recent_files
+            .retain(|recent_file| recent_file != file_path);
+        self.recent_files.insert(0, file_path.to_path_buf());
+        self.recent_files.truncate(MAX_RECENT_FILES);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Fresh directory per test, tests run in parallel
+    fn settings_path(test_name: &str, settings_json: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!(
+            "material_editor_settings_{test_name}_{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+
+        let settings_path = directory.join("user_settings.json");
+        fs::write(&settings_path, settings_json).unwrap();
+        settings_path
+    }
+
+    fn backups(settings_path: &Path) -> Vec<PathBuf> {
+        fs::read_dir(settings_path.parent().unwrap())
+            .unwrap()
+            .filter_map(|dir_entry| Some(dir_entry.ok()?.path()))
+            .filter(|path| path.extension().is_some_and(|extension| extension == "bak"))
+            .collect()
+    }
+
+    #[test]
+    fn version_0_settings_are_migrated() {
+        let settings_path = settings_path(
+            "migration",
+            r#"{ "shader_directory": "", "texture_directories": ["textures"] }"#,
+        );
+
+        let settings = UserSettings::load(&settings_path);
+        assert_eq!(settings.version, MATERIAL_EDITOR_VERSION);
+        assert_eq!(settings.shader_directory, PathBuf::from("./"));
+        assert_eq!(settings.texture_directories[0], PathBuf::from("textures"));
+        assert_eq!(settings.window, WindowGeometry::default());
+
+        // Saved back so the migration only runs once
+        let saved: Value =
+            serde_json::from_str(&fs::read_to_string(&settings_path).unwrap()).unwrap();
+        assert_eq!(saved["version"], MATERIAL_EDITOR_VERSION);
+        assert!(backups(&settings_path).is_empty());
+    }
+
+    #[test]
+    fn newer_settings_keep_unknown_keys_when_saved() {
+        let settings_path = settings_path(
+            "future_version",
+            &format!(
+                r#"{{ "version": {}, "shader_directory": "shaders", "theme": {{ "accent": "teal" }} }}"#,
+                MATERIAL_EDITOR_VERSION + 1
+            ),
+        );
+
+        let mut settings = UserSettings::load(&settings_path);
+        assert_eq!(settings.shader_directory, PathBuf::from("shaders"));
+        settings.add_recent_file(Path::new("shaders/default_sprite.toml"));
+        settings.save(&settings_path);
+
+        let saved: Value =
+            serde_json::from_str(&fs::read_to_string(&settings_path).unwrap()).unwrap();
+        assert_eq!(saved["version"], MATERIAL_EDITOR_VERSION + 1);
+        assert_eq!(saved["theme"]["accent"], "teal");
+        assert_eq!(saved["recent_files"][0], "shaders/default_sprite.toml");
+    }
+
+    #[test]
+    fn invalid_json_is_backed_up() {
+        let settings_path = settings_path("invalid_json", r#"{ "shader_directory": "shaders", "#);
+
+        let settings = UserSettings::load(&settings_path);
+        assert_eq!(settings.shader_directory, PathBuf::from("./"));
+
+        let backups = backups(&settings_path);
+        assert_eq!(backups.len(), 1);
+        assert_eq!(
+            fs::read_to_string(&backups[0]).unwrap(),
+            r#"{ "shader_directory": "shaders", "#
+        );
+    }
+
+    #[test]
+    fn invalid_fields_only_reset_themselves() {
+        let settings_path = settings_path(
+            "invalid_field",
+            &format!(
+                r#"{{ "version": {MATERIAL_EDITOR_VERSION}, "shader_directory": "shaders", "recent_files": 3, "window": {{ "maximized": true }} }}"#
+            ),
+        );
+
+        let settings = UserSettings::load(&settings_path);
+        assert_eq!(settings.shader_directory, PathBuf::from("shaders"));
+        assert!(settings.recent_files.is_empty());
+        assert!(settings.window.maximized);
+        assert_eq!(backups(&settings_path).len(), 1);
+    }
+}
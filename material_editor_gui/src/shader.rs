@@ -0,0 +1,177 @@
+use crate::material::{self, MaterialSections};
+
+// Each texture is bound followed by its sampler, in declaration order
+const TEXTURE_BIND_GROUP: u32 = 2;
+
+// Fields the engine puts first in every material's SceneInstance, before the material's uniforms
+static RESERVED_UNIFORMS: [&str; 3] = [
+    "  local_to_world: mat4x4f,",
+    "  color: vec4f,",
+    "  uv_scale_offset: vec4f,",
+];
+
+// The engine's insertion points, filled from the material by assemble_shader
+static INSERTION_POINTS: [&str; 4] = [
+    "%uniforms%",
+    "%textures%",
+    "%get_world_offset%",
+    "%get_fragment_color%",
+];
+
+/// Sprite shader in the engine's template format, used when no other template is given.
+pub static SPRITE_TEMPLATE: &str = include_str!("sprite_template.wgsl");
+
+/// Checks `template` has every insertion point a material is put into, so a template in another
+/// format fails clearly instead of validating the template alone.
+pub fn check_template(template: &str) -> Result<(), String> {
+    let missing: Vec<&str> = INSERTION_POINTS
+        .into_iter()
+        .filter(|insertion_point| !template.contains(insertion_point))
+        .collect();
+
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("missing insertion points {}", missing.join(", ")))
+    }
+}
+
+/// Fills `template` with the material's uniforms, texture bindings and snippets, the way the engine does.
+///
+/// `%uniforms%` becomes the `SceneInstance` struct, `%textures%` the texture and sampler bindings,
+/// `%get_world_offset%` and `%get_fragment_color%` the function bodies.
+pub fn assemble_shader(sections: &MaterialSections, template: &str) -> String {
+    let uniform_fields = sections
+        .uniforms
+        .parse::<toml::Table>()
+        .map(|uniforms| {
+            uniforms
+                .iter()
+                .filter_map(|(name, value)| {
+                    let uniform_type = match value {
+                        toml::Value::String(uniform_type) => uniform_type.as_str(),
+                        toml::Value::Table(table) => table.get("type")?.as_str()?,
+                        _ => return None,
+                    };
+                    Some(format!("  {name}: {uniform_type},"))
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    // The engine also pads the struct to a fixed size, which doesn't change what validates
+    let scene_instance = format!(
+        "struct SceneInstance {{\n{}\n}}",
+        RESERVED_UNIFORMS
+            .iter()
+            .map(|field| field.to_string())
+            .chain(uniform_fields)
+            .collect::<Vec<_>>()
+            .join("\n")
+    );
+
+    let texture_bindings = material::parse_texture_bindings(&sections.textures)
+        .iter()
+        .enumerate()
+        .map(|(slot, binding)| {
+            let binding_index = slot as u32 * 2;
+            let sampler_index = binding_index + 1;
+            format!(
+                "@group({TEXTURE_BIND_GROUP}) @binding({binding_index}) var {name}: texture_2d<f32>;\n@group({TEXTURE_BIND_GROUP}) @binding({sampler_index}) var sampler_{name}: sampler;",
+                name = binding.name,
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    template
+        .replace("%uniforms%", &scene_instance)
+        .replace("%textures%", &texture_bindings)
+        .replace("%get_world_offset%", &sections.world_offset)
+        .replace("%get_fragment_color%", &sections.frag_color)
+}
+
+/// Parses and validates the material's WGSL with naga once it is put into `template`.
+pub fn validate_wgsl(sections: &MaterialSections, template: &str) -> Result<(), String> {
+    let shader = assemble_shader(sections, template);
+
+    let module =
+        naga::front::wgsl::parse_str(&shader).map_err(|error| error.emit_to_string(&shader))?;
+    naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::all(),
+    )
+    .validate(&module)
+    .map_err(|error| error.emit_to_string(&shader))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // binaries/material_editor_win64/assets/shaders/default_sprite.toml
+    static DEFAULT_SPRITE: &str = r#"get_world_offset = """
+return vec2f(0., 0.);
+"""
+
+get_fragment_color = """
+return scene_instance.temp_vec4_var * textureSample(color_tex, sampler_color_tex, uv0.xy);"""
+
+[uniform_types]
+color_param_1 = "vec4f"
+temp_vec4_var = { type = "vec4f", default = [1.0, 1.0, 1.0, 1.0] }
+
+[texture_descs]
+color_tex = "linear"
+"#;
+
+    fn default_sprite() -> MaterialSections {
+        MaterialSections::from_toml(DEFAULT_SPRITE).0
+    }
+
+    #[test]
+    fn shipped_template_has_every_insertion_point() {
+        assert_eq!(check_template(SPRITE_TEMPLATE), Ok(()));
+        assert!(check_template("{{uniform_fields}} %textures%").is_err());
+    }
+
+    #[test]
+    fn material_fills_the_insertion_points() {
+        let shader = assemble_shader(&default_sprite(), SPRITE_TEMPLATE);
+
+        assert!(!shader.contains('%'));
+        assert!(shader.contains(
+            "struct SceneInstance {\n  local_to_world: mat4x4f,\n  color: vec4f,\n  uv_scale_offset: vec4f,\n  color_param_1: vec4f,\n  temp_vec4_var: vec4f,\n}"
+        ));
+        assert!(shader.contains(
+            "@group(2) @binding(0) var color_tex: texture_2d<f32>;\n@group(2) @binding(1) var sampler_color_tex: sampler;"
+        ));
+    }
+
+    #[test]
+    fn default_sprite_is_valid_wgsl() {
+        assert_eq!(validate_wgsl(&default_sprite(), SPRITE_TEMPLATE), Ok(()));
+    }
+
+    #[test]
+    fn broken_snippets_are_invalid_wgsl() {
+        let undeclared_texture = MaterialSections {
+            frag_color: "return textureSample(normal_tex, sampler_normal_tex, uv0.xy);".to_string(),
+            ..default_sprite()
+        };
+        assert!(validate_wgsl(&undeclared_texture, SPRITE_TEMPLATE).is_err());
+
+        let missing_uniform = MaterialSections {
+            frag_color: "return scene_instance.tint;".to_string(),
+            ..default_sprite()
+        };
+        assert!(validate_wgsl(&missing_uniform, SPRITE_TEMPLATE).is_err());
+
+        let wrong_return_type = MaterialSections {
+            world_offset: "return vec4f(0.);".to_string(),
+            ..default_sprite()
+        };
+        assert!(validate_wgsl(&wrong_return_type, SPRITE_TEMPLATE).is_err());
+    }
+}
@@ -0,0 +1,537 @@
+use std::{cmp::Reverse, collections::BTreeMap};
+
+use eframe::egui::{
+    self, Align, Align2, Event, Key, KeyboardShortcut, Layout, Modifiers, RichText, ScrollArea,
+    TextEdit,
+};
+use serde::{Deserialize, Serialize};
+
+/// Editor actions that can be bound to a keyboard shortcut and run from the command palette.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Compile,
+    Save,
+    SaveAs,
+    Load,
+    Undo,
+    Redo,
+    ToggleLive,
+    NewMaterial,
+    CloseMaterial,
+    NextTab,
+    PreviousTab,
+    ToggleWorkspace,
+    FindReplace,
+    RenameSymbol,
+    ResetLayout,
+    CommandPalette,
+    EditShortcuts,
+}
+
+impl Action {
+    pub const ALL: [Action; 17] = [
+        Action::Compile,
+        Action::Save,
+        Action::SaveAs,
+        Action::Load,
+        Action::Undo,
+        Action::Redo,
+        Action::ToggleLive,
+        Action::NewMaterial,
+        Action::CloseMaterial,
+        Action::NextTab,
+        Action::PreviousTab,
+        Action::ToggleWorkspace,
+        Action::FindReplace,
+        Action::RenameSymbol,
+        Action::ResetLayout,
+        Action::CommandPalette,
+        Action::EditShortcuts,
+    ];
+
+    // Key of the action's binding in the user settings
+    fn id(self) -> &'static str {
+        match self {
+            Action::Compile => "compile",
+            Action::Save => "save",
+            Action::SaveAs => "save_as",
+            Action::Load => "load",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+            Action::ToggleLive => "toggle_live",
+            Action::NewMaterial => "new_material",
+            Action::CloseMaterial => "close_material",
+            Action::NextTab => "next_tab",
+            Action::PreviousTab => "previous_tab",
+            Action::ToggleWorkspace => "toggle_workspace",
+            Action::FindReplace => "find_replace",
+            Action::RenameSymbol => "rename_symbol",
+            Action::ResetLayout => "reset_layout",
+            Action::CommandPalette => "command_palette",
+            Action::EditShortcuts => "edit_shortcuts",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Compile => "Compile",
+            Action::Save => "Save",
+            Action::SaveAs => "Save As...",
+            Action::Load => "Load...",
+            Action::Undo => "Undo",
+            Action::Redo => "Redo",
+            Action::ToggleLive => "Toggle Live Compile",
+            Action::NewMaterial => "New Material",
+            Action::CloseMaterial => "Close Material",
+            Action::NextTab => "Next Tab",
+            Action::PreviousTab => "Previous Tab",
+            Action::ToggleWorkspace => "Toggle Workspace Panel",
+            Action::FindReplace => "Find and Replace...",
+            Action::RenameSymbol => "Rename Symbol...",
+            Action::ResetLayout => "Reset Panel Layout",
+            Action::CommandPalette => "Command Palette",
+            Action::EditShortcuts => "Edit Keyboard Shortcuts...",
+        }
+    }
+
+    // Empty when unbound
+    fn default_binding(self) -> &'static str {
+        match self {
+            Action::Compile => "F5",
+            Action::Save => "Ctrl+S",
+            Action::SaveAs => "Ctrl+Shift+S",
+            Action::Load => "Ctrl+O",
+            Action::Undo => "Ctrl+Z",
+            Action::Redo => "Ctrl+Shift+Z",
+            Action::ToggleLive => "Ctrl+L",
+            Action::NewMaterial => "Ctrl+N",
+            Action::CloseMaterial => "Ctrl+W",
+            Action::NextTab => "Ctrl+PageDown",
+            Action::PreviousTab => "Ctrl+PageUp",
+            Action::ToggleWorkspace => "Ctrl+B",
+            Action::FindReplace => "Ctrl+F",
+            Action::RenameSymbol => "F2",
+            Action::ResetLayout => "",
+            Action::CommandPalette => "Ctrl+Shift+P",
+            Action::EditShortcuts => "",
+        }
+    }
+}
+
+// Shortcuts are written like `Ctrl+Shift+S`. Ctrl is Cmd on macOS
+fn parse_shortcut(text: &str) -> Option<KeyboardShortcut> {
+    let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+    let key = Key::from_name(parts.pop()?)?;
+
+    let mut modifiers = Modifiers::NONE;
+    for part in parts {
+        match part.to_lowercase().as_str() {
+            "ctrl" | "cmd" => modifiers |= Modifiers::COMMAND,
+            "shift" => modifiers |= Modifiers::SHIFT,
+            "alt" => modifiers |= Modifiers::ALT,
+            _ => return None,
+        }
+    }
+    Some(KeyboardShortcut::new(modifiers, key))
+}
+
+fn shortcut_text(shortcut: &KeyboardShortcut) -> String {
+    let modifiers = shortcut.modifiers;
+    let mut text = String::new();
+    if modifiers.command || modifiers.ctrl || modifiers.mac_cmd {
+        text += "Ctrl+";
+    }
+    if modifiers.shift {
+        text += "Shift+";
+    }
+    if modifiers.alt {
+        text += "Alt+";
+    }
+    text + shortcut.logical_key.name()
+}
+
+fn modifier_count(modifiers: Modifiers) -> usize {
+    [modifiers.command, modifiers.shift, modifiers.alt]
+        .into_iter()
+        .filter(|modifier| *modifier)
+        .count()
+}
+
+// Keys that edit text, left to a focused text box when pressed without modifiers
+fn is_text_key(key: Key) -> bool {
+    key.name().chars().count() == 1
+        || matches!(
+            key,
+            Key::Space | Key::Enter | Key::Tab | Key::Backspace | Key::Delete
+        )
+}
+
+/// Shortcut of each action by action id, ex: `"save": "Ctrl+S"`. Actions missing here use their
+/// default shortcut, an empty shortcut leaves the action unbound.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(transparent)]
+pub struct KeyBindings(BTreeMap<String, String>);
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self(
+            Action::ALL
+                .into_iter()
+                .map(|action| {
+                    (
+                        action.id().to_string(),
+                        action.default_binding().to_string(),
+                    )
+                })
+                .collect(),
+        )
+    }
+}
+
+impl KeyBindings {
+    pub fn shortcut(&self, action: Action) -> Option<KeyboardShortcut> {
+        match self.0.get(action.id()) {
+            Some(binding) => parse_shortcut(binding),
+            None => parse_shortcut(action.default_binding()),
+        }
+    }
+
+    /// The action's shortcut for tooltips and menus, empty when unbound.
+    pub fn text(&self, action: Action) -> String {
+        self.shortcut(action)
+            .map(|shortcut| shortcut_text(&shortcut))
+            .unwrap_or_default()
+    }
+
+    /// Binds `shortcut` to `action`, taking it away from any other action it was bound to.
+    pub fn set(&mut self, action: Action, shortcut: Option<KeyboardShortcut>) {
+        let text = shortcut
+            .map(|shortcut| shortcut_text(&shortcut))
+            .unwrap_or_default();
+        if !text.is_empty() {
+            for other_action in Action::ALL {
+                if other_action != action && self.text(other_action) == text {
+                    self.0.insert(other_action.id().to_string(), String::new());
+                }
+            }
+        }
+        self.0.insert(action.id().to_string(), text);
+    }
+
+    /// Settings bindings that aren't valid shortcuts, these actions are left unbound.
+    pub fn invalid_bindings(&self) -> Vec<&str> {
+        self.0
+            .values()
+            .filter(|binding| !binding.is_empty() && parse_shortcut(binding).is_none())
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// The action whose shortcut was pressed this frame, its key press is consumed.
+    pub fn consume(&self, ctx: &egui::Context) -> Option<Action> {
+        let mut shortcuts: Vec<(Action, KeyboardShortcut)> = Action::ALL
+            .into_iter()
+            .filter_map(|action| Some((action, self.shortcut(action)?)))
+            .collect();
+        // Egui also matches Ctrl+Z when Ctrl+Shift+Z is pressed, so the more specific shortcuts go first
+        shortcuts.sort_by_key(|(_, shortcut)| Reverse(modifier_count(shortcut.modifiers)));
+
+        let typing = ctx.wants_keyboard_input();
+        ctx.input_mut(|input| {
+            shortcuts
+                .into_iter()
+                .filter(|(_, shortcut)| {
+                    !(typing && shortcut.modifiers.is_none() && is_text_key(shortcut.logical_key))
+                })
+                .find(|(_, shortcut)| input.consume_shortcut(shortcut))
+                .map(|(action, _)| action)
+        })
+    }
+}
+
+/// Searchable list of every action, opened with Ctrl+Shift+P by default.
+#[derive(Default)]
+pub struct CommandPalette {
+    is_open: bool,
+    query: String,
+    selected: usize,
+}
+
+impl CommandPalette {
+    pub fn open(&mut self) {
+        *self = Self {
+            is_open: true,
+            ..Self::default()
+        };
+    }
+
+    /// Returns the action picked with Enter or a click, the palette closes afterwards.
+    pub fn show(&mut self, ctx: &egui::Context, key_bindings: &KeyBindings) -> Option<Action> {
+        if !self.is_open {
+            return None;
+        }
+
+        let query = self.query.to_lowercase();
+        let actions: Vec<Action> = Action::ALL
+            .into_iter()
+            .filter(|action| *action != Action::CommandPalette)
+            .filter(|action| action.name().to_lowercase().contains(&query))
+            .collect();
+        self.selected = self.selected.min(actions.len().saturating_sub(1));
+
+        // Consumed before the query box sees them
+        let (up, down, enter, escape) = ctx.input_mut(|input| {
+            (
+                input.consume_key(Modifiers::NONE, Key::ArrowUp),
+                input.consume_key(Modifiers::NONE, Key::ArrowDown),
+                input.consume_key(Modifiers::NONE, Key::Enter),
+                input.consume_key(Modifiers::NONE, Key::Escape),
+            )
+        });
+        if up {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        if down && self.selected + 1 < actions.len() {
+            self.selected += 1;
+        }
+
+        let mut picked = enter.then(|| actions.get(self.selected).copied()).flatten();
+        let window = egui::Window::new("Command Palette")
+            .title_bar(false)
+            .resizable(false)
+            .collapsible(false)
+            .anchor(Align2::CENTER_TOP, [0., 60.])
+            .show(ctx, |ui| {
+                let query_box = ui.add(
+                    TextEdit::singleline(&mut self.query)
+                        .hint_text("Type a command")
+                        .desired_width(320.),
+                );
+                query_box.request_focus();
+                if query_box.changed() {
+                    self.selected = 0;
+                }
+
+                ScrollArea::vertical().max_height(300.).show(ui, |ui| {
+                    for (index, action) in actions.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            let entry = ui.selectable_label(index == self.selected, action.name());
+                            if index == self.selected && (up || down) {
+                                entry.scroll_to_me(None);
+                            }
+                            if entry.clicked() {
+                                picked = Some(*action);
+                            }
+                            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                                ui.label(RichText::new(key_bindings.text(*action)).weak());
+                            });
+                        });
+                    }
+                    if actions.is_empty() {
+                        ui.weak("No matching commands");
+                    }
+                });
+            });
+
+        let clicked_outside = window.is_some_and(|window| {
+            ctx.input(|input| input.pointer.any_pressed()) && !window.response.contains_pointer()
+        });
+        if picked.is_some() || escape || clicked_outside {
+            self.is_open = false;
+        }
+        picked
+    }
+}
+
+/// Window listing every action's shortcut, clicking one records the next key press as its new
+/// shortcut.
+#[derive(Default)]
+pub struct ShortcutEditor {
+    pub is_open: bool,
+    recording: Option<Action>,
+}
+
+impl ShortcutEditor {
+    /// Takes the key press being recorded before any shortcut can react to it. Returns whether a
+    /// binding changed.
+    pub fn record_key(&mut self, ctx: &egui::Context, key_bindings: &mut KeyBindings) -> bool {
+        let Some(action) = self.recording else {
+            return false;
+        };
+
+        let shortcut = ctx.input_mut(|input| {
+            let mut shortcut = None;
+            input.events.retain(|event| match event {
+                Event::Key {
+                    key,
+                    pressed: true,
+                    modifiers,
+                    ..
+                } if shortcut.is_none() => {
+                    shortcut = Some(KeyboardShortcut::new(*modifiers, *key));
+                    false
+                }
+                _ => true,
+            });
+            shortcut
+        });
+
+        match shortcut {
+            None => false,
+            // Escape cancels rather than becoming the shortcut
+            Some(shortcut)
+                if shortcut.logical_key == Key::Escape && shortcut.modifiers.is_none() =>
+            {
+                self.recording = None;
+                false
+            }
+            Some(shortcut) => {
+                key_bindings.set(action, Some(shortcut));
+                self.recording = None;
+                true
+            }
+        }
+    }
+
+    /// Returns whether a binding changed.
+    pub fn show(&mut self, ctx: &egui::Context, key_bindings: &mut KeyBindings) -> bool {
+        let mut changed = false;
+        let mut is_open = self.is_open;
+
+        egui::Window::new("Keyboard Shortcuts")
+            .open(&mut is_open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label("Click a shortcut and press the new key combination, Escape cancels");
+                ui.separator();
+
+                egui::Grid::new("keyboard_shortcuts")
+                    .num_columns(3)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for action in Action::ALL {
+                            ui.label(action.name());
+
+                            let shortcut_text = if self.recording == Some(action) {
+                                "Press a key...".to_string()
+                            } else {
+                                match key_bindings.text(action) {
+                                    text if text.is_empty() => "Unbound".to_string(),
+                                    text => text,
+                                }
+                            };
+                            if ui
+                                .selectable_label(self.recording == Some(action), shortcut_text)
+                                .on_hover_text(
+                                    "A shortcut already bound to another action is moved here",
+                                )
+                                .clicked()
+                            {
+                                self.recording = Some(action);
+                            }
+
+                            if ui
+                                .add_enabled(
+                                    key_bindings.shortcut(action).is_some(),
+                                    egui::Button::new("Clear").small(),
+                                )
+                                .clicked()
+                            {
+                                key_bindings.set(action, None);
+                                changed = true;
+                            }
+                            ui.end_row();
+                        }
+                    });
+
+                ui.separator();
+                if ui.button("Reset to Defaults").clicked() {
+                    *key_bindings = KeyBindings::default();
+                    self.recording = None;
+                    changed = true;
+                }
+            });
+
+        self.is_open = is_open;
+        if !self.is_open {
+            self.recording = None;
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bindings(settings_json: &str) -> KeyBindings {
+        serde_json::from_str(settings_json).unwrap()
+    }
+
+    #[test]
+    fn shortcuts_parse_and_format_the_same_text() {
+        assert_eq!(
+            parse_shortcut("Ctrl+S"),
+            Some(KeyboardShortcut::new(Modifiers::COMMAND, Key::S))
+        );
+        assert_eq!(
+            parse_shortcut("Ctrl+Shift+Z"),
+            Some(KeyboardShortcut::new(
+                Modifiers::COMMAND | Modifiers::SHIFT,
+                Key::Z
+            ))
+        );
+
+        for text in ["Ctrl+S", "Ctrl+Shift+Z", "Alt+F4", "F5", "Ctrl+PageDown"] {
+            assert_eq!(shortcut_text(&parse_shortcut(text).unwrap()), text);
+        }
+        // Hand edited bindings are written back in the usual form
+        assert_eq!(
+            shortcut_text(&parse_shortcut("cmd + shift + z").unwrap()),
+            "Ctrl+Shift+Z"
+        );
+    }
+
+    #[test]
+    fn unknown_shortcuts_leave_the_action_unbound() {
+        assert_eq!(parse_shortcut("Hyper+S"), None);
+        assert_eq!(parse_shortcut("Ctrl+Banana"), None);
+        assert_eq!(parse_shortcut(""), None);
+
+        let key_bindings = bindings(r#"{ "save": "Hyper+S", "compile": "", "undo": "Ctrl+Y" }"#);
+        assert_eq!(key_bindings.invalid_bindings(), ["Hyper+S"]);
+        assert_eq!(key_bindings.text(Action::Save), "");
+        assert_eq!(key_bindings.text(Action::Compile), "");
+        assert_eq!(key_bindings.text(Action::Undo), "Ctrl+Y");
+        // Actions missing from the settings keep their default
+        assert_eq!(key_bindings.text(Action::Redo), "Ctrl+Shift+Z");
+    }
+
+    #[test]
+    fn binding_a_used_shortcut_moves_it() {
+        let mut key_bindings = KeyBindings::default();
+        key_bindings.set(Action::Compile, parse_shortcut("Ctrl+S"));
+
+        assert_eq!(key_bindings.text(Action::Compile), "Ctrl+S");
+        assert_eq!(key_bindings.text(Action::Save), "");
+        assert_eq!(key_bindings.text(Action::SaveAs), "Ctrl+Shift+S");
+
+        key_bindings.set(Action::Compile, None);
+        assert_eq!(key_bindings.shortcut(Action::Compile), None);
+        assert!(key_bindings.invalid_bindings().is_empty());
+    }
+
+    #[test]
+    fn default_bindings_are_unique() {
+        let key_bindings = KeyBindings::default();
+        let texts: Vec<String> = Action::ALL
+            .into_iter()
+            .map(|action| key_bindings.text(action))
+            .filter(|text| !text.is_empty())
+            .collect();
+
+        for (index, text) in texts.iter().enumerate() {
+            assert!(!texts[index + 1..].contains(text), "{text} is bound twice");
+        }
+    }
+}
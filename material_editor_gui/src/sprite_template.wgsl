@@ -0,0 +1,56 @@
+// Sprite shader materials are validated against, with the engine's insertion points.
+// The engine generates the SceneInstance struct and texture bindings from the material toml the same way
+// shader.rs does, the rest of its template doesn't ship with the editor, pass it with --template to
+// validate against it instead.
+
+struct GlobalUniforms {
+  view_projection: mat4x4f,
+}
+
+@group(0) @binding(0) var<uniform> global_uniforms: GlobalUniforms;
+
+%uniforms%
+
+@group(1) @binding(0) var<storage, read> scene_instances: array<SceneInstance>;
+
+%textures%
+
+struct VertexInput {
+  @location(0) position: vec2f,
+  @location(1) uv: vec2f,
+}
+
+struct VertexOutput {
+  @builtin(position) position: vec4f,
+  @location(0) uv0: vec4f,
+  @location(1) @interpolate(flat) instance_index: u32,
+}
+
+fn get_world_offset(scene_instance: SceneInstance, uv0: vec4f) -> vec2f {
+%get_world_offset%
+}
+
+fn get_fragment_color(scene_instance: SceneInstance, uv0: vec4f) -> vec4f {
+%get_fragment_color%
+}
+
+@vertex
+fn vs_main(vertex: VertexInput, @builtin(instance_index) instance_index: u32) -> VertexOutput {
+  let scene_instance = scene_instances[instance_index];
+  let uv = vertex.uv * scene_instance.uv_scale_offset.xy + scene_instance.uv_scale_offset.zw;
+  let uv0 = vec4f(uv, 0., 0.);
+  let world_position = scene_instance.local_to_world * vec4f(vertex.position, 0., 1.)
+    + vec4f(get_world_offset(scene_instance, uv0), 0., 0.);
+
+  var out: VertexOutput;
+  out.position = global_uniforms.view_projection * world_position;
+  out.uv0 = uv0;
+  out.instance_index = instance_index;
+  return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4f {
+  let scene_instance = scene_instances[in.instance_index];
+  return scene_instance.color * get_fragment_color(scene_instance, in.uv0);
+}
@@ -0,0 +1,497 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Component, Path, PathBuf},
+};
+
+use eframe::egui::{
+    self, ColorImage, ComboBox, Grid, Image, ScrollArea, TextureHandle, TextureOptions,
+};
+use image::{ImageDecoder, ImageReader};
+
+use crate::material::{FilterMode, SamplerDesc};
+
+pub const THUMBNAIL_SIZE: f32 = 32.;
+static ASSET_TEXTURES_DIR: &str = "textures";
+const BROWSER_THUMBNAIL_SIZE: f32 = 64.;
+const TEXTURE_EXTENSIONS: [&str; 1] = ["png"];
+
+/// Uri egui's image loaders use to display the texture at `path`.
+pub fn texture_uri(path: &Path) -> String {
+    format!("file://{}", path.to_string_lossy())
+}
+
+pub fn thumbnail(path: &Path, size: f32) -> Image<'static> {
+    Image::new(texture_uri(path)).fit_to_exact_size(egui::vec2(size, size))
+}
+
+/// Path of `path` relative to `asset_root` with `/` separators, the form scenes reference textures by.
+/// ex: `<asset_root>/textures/star.png` -> `textures/star.png`. None when `path` lies outside the asset tree.
+pub fn asset_relative_path(asset_root: &Path, path: &Path) -> Option<PathBuf> {
+    let path = path.canonicalize().ok()?;
+    let relative_path = path.strip_prefix(asset_root).ok()?;
+
+    let components: Vec<_> = relative_path
+        .components()
+        .map(|component| match component {
+            Component::Normal(name) => Some(name.to_string_lossy()),
+            _ => None,
+        })
+        .collect::<Option<_>>()?;
+
+    Some(components.join("/").into())
+}
+
+/// Copies a texture into the asset tree's textures directory and returns its asset-relative path.
+/// An existing file of the same name is never overwritten, a numbered name is used instead.
+pub fn copy_into_assets(asset_root: &Path, path: &Path) -> io::Result<PathBuf> {
+    let file_stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path.extension().unwrap_or_default().to_string_lossy();
+    let textures_dir = asset_root.join(ASSET_TEXTURES_DIR);
+    fs::create_dir_all(&textures_dir)?;
+
+    let mut file_name = format!("{file_stem}.{extension}");
+    let mut copy_index = 1;
+    while textures_dir.join(&file_name).exists() {
+        file_name = format!("{file_stem}_{copy_index}.{extension}");
+        copy_index += 1;
+    }
+
+    fs::copy(path, textures_dir.join(&file_name))?;
+    Ok(format!("{ASSET_TEXTURES_DIR}/{file_name}").into())
+}
+
+/// Filter picker for a binding's sampler. Returns true when the sampler changed.
+pub fn sampler_editor(ui: &mut egui::Ui, binding_name: &str, sampler: &mut SamplerDesc) -> bool {
+    let previous_sampler = *sampler;
+
+    ComboBox::from_id_salt(("sampler_filter", binding_name))
+        .width(64.)
+        .selected_text(sampler.filter.name())
+        .show_ui(ui, |ui| {
+            for filter in FilterMode::ALL {
+                ui.selectable_value(&mut sampler.filter, filter, filter.name());
+            }
+        });
+
+    *sampler != previous_sampler
+}
+
+pub fn format_file_size(bytes: u64) -> String {
+    match bytes {
+        0..1024 => format!("{bytes} B"),
+        1024..1_048_576 => format!("{:.1} KB", bytes as f64 / 1024.),
+        _ => format!("{:.1} MB", bytes as f64 / 1_048_576.),
+    }
+}
+
+/// A texture the engine module loaded on the editor's behalf, as reported in its `loaded_textures` message.
+#[derive(Debug, Clone)]
+pub struct EngineTexture {
+    pub path: String,
+    pub id: String,
+    pub width: u32,
+    pub height: u32,
+    pub size_in_bytes: u64,
+}
+
+/// Parses the `path \t tex id \t width \t height \t size in bytes` lines of a `loaded_textures` message.
+pub fn parse_engine_textures(message: &str) -> Vec<EngineTexture> {
+    let mut engine_textures: Vec<EngineTexture> = message
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split('\t').collect();
+            let [path, id, width, height, size_in_bytes] = fields[..] else {
+                return None;
+            };
+
+            Some(EngineTexture {
+                path: path.to_string(),
+                id: id.to_string(),
+                width: width.parse().unwrap_or_default(),
+                height: height.parse().unwrap_or_default(),
+                size_in_bytes: size_in_bytes.parse().unwrap_or_default(),
+            })
+        })
+        .collect();
+    engine_textures.sort_by(|a, b| a.path.cmp(&b.path));
+    engine_textures
+}
+
+/// Lists the textures the engine loaded for the editor.
+pub fn show_engine_textures(ui: &mut egui::Ui, engine_textures: &[EngineTexture]) {
+    if engine_textures.is_empty() {
+        ui.label("No textures loaded by the editor");
+        return;
+    }
+
+    Grid::new("engine_textures")
+        .num_columns(4)
+        .striped(true)
+        .show(ui, |ui| {
+            ui.strong("Texture");
+            ui.strong("Id");
+            ui.strong("Size");
+            ui.strong("Memory");
+            ui.end_row();
+
+            for engine_texture in engine_textures {
+                ui.label(
+                    Path::new(&engine_texture.path)
+                        .file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy(),
+                )
+                .on_hover_text(&engine_texture.path);
+                ui.label(&engine_texture.id);
+                ui.label(format!(
+                    "{}x{}",
+                    engine_texture.width, engine_texture.height
+                ));
+                ui.label(format_file_size(engine_texture.size_in_bytes));
+                ui.end_row();
+            }
+        });
+}
+
+/// Header information of a texture file, read without decoding its pixels.
+#[derive(Debug, Clone)]
+pub struct TextureInfo {
+    pub width: u32,
+    pub height: u32,
+    pub format: String,
+    pub file_size: u64,
+}
+
+impl TextureInfo {
+    pub fn read(path: &Path) -> Option<Self> {
+        let file_size = fs::metadata(path).ok()?.len();
+        let reader = ImageReader::open(path).ok()?.with_guessed_format().ok()?;
+        let file_format = reader.format()?;
+        let decoder = reader.into_decoder().ok()?;
+        let (width, height) = decoder.dimensions();
+
+        Some(Self {
+            width,
+            height,
+            format: format!("{file_format:?} {:?}", decoder.color_type()),
+            file_size,
+        })
+    }
+
+    /// ex: `512x512 Png Rgba8, 12.3 KB`
+    pub fn summary(&self) -> String {
+        format!(
+            "{}x{} {}, {}",
+            self.width,
+            self.height,
+            self.format,
+            format_file_size(self.file_size)
+        )
+    }
+}
+
+/// Caches [`TextureInfo`] per path so headers are only read once.
+#[derive(Default)]
+pub struct TextureInfoCache {
+    infos: HashMap<PathBuf, Option<TextureInfo>>,
+}
+
+impl TextureInfoCache {
+    pub fn get(&mut self, path: &Path) -> Option<&TextureInfo> {
+        self.infos
+            .entry(path.to_path_buf())
+            .or_insert_with(|| TextureInfo::read(path))
+            .as_ref()
+    }
+
+    pub fn invalidate(&mut self, path: &Path) {
+        self.infos.remove(path);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Channel {
+    Rgba,
+    Red,
+    Green,
+    Blue,
+    Alpha,
+}
+
+impl Channel {
+    const ALL: [Channel; 5] = [
+        Channel::Rgba,
+        Channel::Red,
+        Channel::Green,
+        Channel::Blue,
+        Channel::Alpha,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Channel::Rgba => "RGBA",
+            Channel::Red => "R",
+            Channel::Green => "G",
+            Channel::Blue => "B",
+            Channel::Alpha => "A",
+        }
+    }
+
+    // Isolated channels are shown as opaque grayscale so masks are easy to read
+    fn isolate(&self, pixel: [u8; 4]) -> [u8; 4] {
+        let value = match self {
+            Channel::Rgba => return pixel,
+            Channel::Red => pixel[0],
+            Channel::Green => pixel[1],
+            Channel::Blue => pixel[2],
+            Channel::Alpha => pixel[3],
+        };
+        [value, value, value, 255]
+    }
+}
+
+/// Window displaying a texture with its R/G/B/A channels viewable in isolation.
+pub struct ChannelViewer {
+    pub path: PathBuf,
+    channel: Channel,
+    pixels: Option<image::RgbaImage>,
+    texture: Option<TextureHandle>,
+}
+
+impl ChannelViewer {
+    pub fn new(path: PathBuf) -> Self {
+        let pixels = match image::open(&path) {
+            Ok(image) => Some(image.to_rgba8()),
+            Err(error) => {
+                println!(
+                    "Failed to decode texture {} with error {error}",
+                    path.to_string_lossy()
+                );
+                None
+            }
+        };
+
+        Self {
+            path,
+            channel: Channel::Rgba,
+            pixels,
+            texture: None,
+        }
+    }
+
+    fn upload(&mut self, ctx: &egui::Context) {
+        let Some(pixels) = &self.pixels else {
+            return;
+        };
+
+        let isolated: Vec<u8> = pixels
+            .pixels()
+            .flat_map(|pixel| self.channel.isolate(pixel.0))
+            .collect();
+        let color_image = ColorImage::from_rgba_unmultiplied(
+            [pixels.width() as usize, pixels.height() as usize],
+            &isolated,
+        );
+
+        self.texture = Some(ctx.load_texture(
+            format!("channel_viewer_{}", self.path.to_string_lossy()),
+            color_image,
+            TextureOptions::NEAREST,
+        ));
+    }
+
+    /// Returns false once the user closes the window.
+    pub fn show(&mut self, ctx: &egui::Context) -> bool {
+        let mut open = true;
+        let title = self
+            .path
+            .file_name()
+            .map(|file_name| file_name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        if self.texture.is_none() {
+            self.upload(ctx);
+        }
+
+        egui::Window::new(format!("Channels - {title}"))
+            .id(egui::Id::new("channel_viewer"))
+            .open(&mut open)
+            .default_size([320., 360.])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    for channel in Channel::ALL {
+                        if ui
+                            .selectable_label(self.channel == channel, channel.label())
+                            .clicked()
+                            && self.channel != channel
+                        {
+                            self.channel = channel;
+                            self.texture = None;
+                        }
+                    }
+                });
+
+                match &self.texture {
+                    Some(texture) => {
+                        ui.add(Image::new(texture).shrink_to_fit());
+                    }
+                    None => {
+                        ui.label("Unable to decode texture");
+                    }
+                }
+            });
+
+        open
+    }
+}
+
+pub enum TextureBrowserAction {
+    None,
+    Picked(PathBuf),
+    BrowseNative,
+    Closed,
+}
+
+/// Window listing the textures of a directory as thumbnails, for picking a binding's texture.
+pub struct TextureBrowser {
+    pub slot: usize,
+    pub binding_name: String,
+    pub directory: PathBuf,
+    entries: Vec<PathBuf>,
+}
+
+impl TextureBrowser {
+    pub fn new(slot: usize, binding_name: String, directory: PathBuf) -> Self {
+        let mut browser = Self {
+            slot,
+            binding_name,
+            directory,
+            entries: Vec::new(),
+        };
+        browser.refresh();
+        browser
+    }
+
+    fn refresh(&mut self) {
+        self.entries = fs::read_dir(&self.directory)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|path| {
+                        path.extension().is_some_and(|extension| {
+                            TEXTURE_EXTENSIONS
+                                .iter()
+                                .any(|known| extension.eq_ignore_ascii_case(known))
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        self.entries.sort();
+    }
+
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        texture_infos: &mut TextureInfoCache,
+    ) -> TextureBrowserAction {
+        let mut action = TextureBrowserAction::None;
+        let mut open = true;
+
+        egui::Window::new(format!("Pick texture for {}", self.binding_name))
+            .id(egui::Id::new("texture_browser"))
+            .open(&mut open)
+            .default_size([360., 420.])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(self.directory.to_string_lossy());
+                    if ui.button("Change Folder...").clicked() {
+                        if let Some(directory) = rfd::FileDialog::new()
+                            .set_directory(&self.directory)
+                            .pick_folder()
+                        {
+                            self.directory = directory;
+                            self.refresh();
+                        }
+                    }
+                    if ui.button("Other File...").clicked() {
+                        action = TextureBrowserAction::BrowseNative;
+                    }
+                });
+
+                if self.entries.is_empty() {
+                    ui.label("No textures in this folder");
+                }
+
+                ScrollArea::vertical().show(ui, |ui| {
+                    Grid::new("texture_browser_grid")
+                        .num_columns(2)
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for path in &self.entries {
+                                let thumbnail_clicked = ui
+                                    .add(
+                                        thumbnail(path, BROWSER_THUMBNAIL_SIZE)
+                                            .sense(egui::Sense::click()),
+                                    )
+                                    .clicked();
+
+                                let file_name = path
+                                    .file_name()
+                                    .map(|file_name| file_name.to_string_lossy().to_string())
+                                    .unwrap_or_default();
+                                let info = texture_infos
+                                    .get(path)
+                                    .map(|info| info.summary())
+                                    .unwrap_or("Unreadable texture".to_string());
+
+                                let label_clicked = ui
+                                    .vertical(|ui| {
+                                        let clicked = ui.button(file_name).clicked();
+                                        ui.label(info);
+                                        clicked
+                                    })
+                                    .inner;
+
+                                if thumbnail_clicked || label_clicked {
+                                    action = TextureBrowserAction::Picked(path.clone());
+                                }
+                                ui.end_row();
+                            }
+                        });
+                });
+            });
+
+        if !open {
+            action = TextureBrowserAction::Closed;
+        }
+
+        action
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_sizes_use_the_largest_fitting_unit() {
+        assert_eq!(format_file_size(0), "0 B");
+        assert_eq!(format_file_size(1023), "1023 B");
+        assert_eq!(format_file_size(1024), "1.0 KB");
+        assert_eq!(format_file_size(12_595), "12.3 KB");
+        assert_eq!(format_file_size(1_048_576), "1.0 MB");
+    }
+
+    #[test]
+    fn isolated_channels_are_opaque_grayscale() {
+        let pixel = [10, 20, 30, 40];
+
+        assert_eq!(Channel::Rgba.isolate(pixel), pixel);
+        assert_eq!(Channel::Red.isolate(pixel), [10, 10, 10, 255]);
+        assert_eq!(Channel::Green.isolate(pixel), [20, 20, 20, 255]);
+        assert_eq!(Channel::Blue.isolate(pixel), [30, 30, 30, 255]);
+        assert_eq!(Channel::Alpha.isolate(pixel), [40, 40, 40, 255]);
+    }
+}
@@ -0,0 +1,227 @@
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use eframe::egui::{self, RichText, ScrollArea};
+
+use crate::{
+    material::MaterialSections,
+    shortcuts::{Action, KeyBindings},
+};
+
+// Edits of the same kind closer together than this undo as one step, so typing doesn't undo per character
+static UNDO_MERGE_INTERVAL: Duration = Duration::from_millis(1000);
+static MAX_UNDO_STEPS: usize = 200;
+
+/// Everything an undo step restores.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EditorSnapshot {
+    pub shader_path: PathBuf,
+    pub sections: MaterialSections,
+    pub texture_assignments: BTreeMap<String, PathBuf>,
+}
+
+impl EditorSnapshot {
+    // Names what changed between two snapshots, ex: `Edit Fragment Color`
+    fn change_label(&self, next: &EditorSnapshot) -> String {
+        if self.shader_path != next.shader_path {
+            return format!(
+                "Load {}",
+                next.shader_path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+            );
+        }
+        if self.texture_assignments != next.texture_assignments {
+            return "Assign texture".to_string();
+        }
+
+        let (sections, next_sections) = (&self.sections, &next.sections);
+        let changed_sections: Vec<&str> = [
+            ("Uniforms", sections.uniforms != next_sections.uniforms),
+            ("Textures", sections.textures != next_sections.textures),
+            (
+                "World Offset",
+                sections.world_offset != next_sections.world_offset,
+            ),
+            (
+                "Fragment Color",
+                sections.frag_color != next_sections.frag_color,
+            ),
+            ("Blend", sections.blend != next_sections.blend),
+        ]
+        .into_iter()
+        .filter_map(|(section, changed)| changed.then_some(section))
+        .collect();
+
+        format!("Edit {}", changed_sections.join(", "))
+    }
+}
+
+struct UndoStep {
+    label: String,
+    // State before the step for undo steps, after it for redo steps
+    snapshot: EditorSnapshot,
+}
+
+pub enum UndoAction {
+    None,
+    Restore(EditorSnapshot),
+}
+
+/// Editor-wide undo and redo, built by comparing the editor state every frame.
+#[derive(Default)]
+pub struct UndoHistory {
+    undo_steps: Vec<UndoStep>,
+    redo_steps: Vec<UndoStep>,
+    current: Option<EditorSnapshot>,
+    current_label: String,
+    last_change: Option<Instant>,
+    // Replaces the generated label of the next change, ex: `Change tint`
+    next_label: Option<String>,
+}
+
+impl UndoHistory {
+    pub fn label_next_change(&mut self, label: impl Into<String>) {
+        self.next_label = Some(label.into());
+    }
+
+    /// Records a step if `snapshot` differs from the state seen last frame.
+    pub fn observe(&mut self, snapshot: EditorSnapshot) {
+        let Some(current) = &self.current else {
+            self.current = Some(snapshot);
+            return;
+        };
+        if *current == snapshot {
+            return;
+        }
+
+        let label = self
+            .next_label
+            .take()
+            .unwrap_or_else(|| current.change_label(&snapshot));
+        let merge = label == self.current_label
+            && self.redo_steps.is_empty()
+            && !self.undo_steps.is_empty()
+            && !label.starts_with("Load")
+            && self
+                .last_change
+                .is_some_and(|last_change| last_change.elapsed() < UNDO_MERGE_INTERVAL);
+
+        if !merge {
+            self.undo_steps.push(UndoStep {
+                label: label.clone(),
+                snapshot: current.clone(),
+            });
+            if self.undo_steps.len() > MAX_UNDO_STEPS {
+                self.undo_steps.remove(0);
+            }
+        }
+
+        self.redo_steps.clear();
+        self.current = Some(snapshot);
+        self.current_label = label;
+        self.last_change = Some(Instant::now());
+    }
+
+    pub fn undo(&mut self) -> UndoAction {
+        let (Some(step), Some(current)) = (self.undo_steps.pop(), self.current.take()) else {
+            return UndoAction::None;
+        };
+
+        self.redo_steps.push(UndoStep {
+            label: step.label.clone(),
+            snapshot: current,
+        });
+        self.restore(step.snapshot)
+    }
+
+    pub fn redo(&mut self) -> UndoAction {
+        let (Some(step), Some(current)) = (self.redo_steps.pop(), self.current.take()) else {
+            return UndoAction::None;
+        };
+
+        self.undo_steps.push(UndoStep {
+            label: step.label.clone(),
+            snapshot: current,
+        });
+        self.restore(step.snapshot)
+    }
+
+    // The restored state becomes current, so restoring it isn't recorded as a new change
+    fn restore(&mut self, snapshot: EditorSnapshot) -> UndoAction {
+        self.current = Some(snapshot.clone());
+        self.current_label.clear();
+        self.next_label = None;
+        UndoAction::Restore(snapshot)
+    }
+
+    pub fn len(&self) -> usize {
+        self.undo_steps.len() + self.redo_steps.len()
+    }
+
+    /// Lists the steps, oldest first, clicking one undoes or redoes up to it.
+    pub fn show(&mut self, ui: &mut egui::Ui, key_bindings: &KeyBindings) -> UndoAction {
+        let mut undo_count = 0;
+        let mut redo_count = 0;
+
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(!self.undo_steps.is_empty(), egui::Button::new("Undo"))
+                .on_hover_text(key_bindings.text(Action::Undo))
+                .clicked()
+            {
+                undo_count = 1;
+            }
+            if ui
+                .add_enabled(!self.redo_steps.is_empty(), egui::Button::new("Redo"))
+                .on_hover_text(key_bindings.text(Action::Redo))
+                .clicked()
+            {
+                redo_count = 1;
+            }
+        });
+
+        ScrollArea::vertical()
+            .id_salt("undo_history_scroll")
+            .max_height(160.)
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                if ui
+                    .selectable_label(self.undo_steps.is_empty(), "Start")
+                    .clicked()
+                {
+                    undo_count = self.undo_steps.len();
+                }
+                for (index, step) in self.undo_steps.iter().enumerate() {
+                    let is_current = index + 1 == self.undo_steps.len();
+                    if ui.selectable_label(is_current, &step.label).clicked() {
+                        undo_count = self.undo_steps.len() - index - 1;
+                    }
+                }
+                for (index, step) in self.redo_steps.iter().rev().enumerate() {
+                    let label = RichText::new(&step.label).color(ui.visuals().weak_text_color());
+                    if ui.selectable_label(false, label).clicked() {
+                        redo_count = index + 1;
+                    }
+                }
+            });
+
+        let mut action = UndoAction::None;
+        for _ in 0..undo_count {
+            if let UndoAction::Restore(snapshot) = self.undo() {
+                action = UndoAction::Restore(snapshot);
+            }
+        }
+        for _ in 0..redo_count {
+            if let UndoAction::Restore(snapshot) = self.redo() {
+                action = UndoAction::Restore(snapshot);
+            }
+        }
+
+        action
+    }
+}
@@ -0,0 +1,187 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver},
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use eframe::egui::{self, RichText, ScrollArea};
+
+use crate::material;
+
+// Checked this often while the panel is shown, so materials added or removed by other programs show up
+static WORKSPACE_SCAN_INTERVAL: Duration = Duration::from_secs(1);
+
+// Modified time of every directory under the workspace, adding, removing or renaming a file changes its directory's
+type DirectoryTimes = Vec<(PathBuf, Option<SystemTime>)>;
+
+struct Scan {
+    materials: Vec<PathBuf>,
+    directory_times: DirectoryTimes,
+}
+
+pub enum WorkspaceAction {
+    None,
+    Open(PathBuf),
+    ChooseDirectory,
+}
+
+/// Material tomls found under the workspace directory.
+pub struct Workspace {
+    pub directory: PathBuf,
+    materials: Vec<PathBuf>,
+    directory_times: DirectoryTimes,
+    last_scan: Option<Instant>,
+    // Scans walk the directory tree on their own thread so large workspaces don't stall the UI.
+    // None when no directory changed since the last scan
+    pending_scan: Option<Receiver<Option<Scan>>>,
+    filter: String,
+}
+
+impl Workspace {
+    pub fn new(directory: &Path) -> Self {
+        Self {
+            directory: directory.canonicalize().unwrap_or(directory.to_path_buf()),
+            materials: Vec::new(),
+            directory_times: Vec::new(),
+            last_scan: None,
+            pending_scan: None,
+            filter: String::new(),
+        }
+    }
+
+    pub fn set_directory(&mut self, directory: &Path) {
+        *self = Self {
+            filter: std::mem::take(&mut self.filter),
+            ..Self::new(directory)
+        };
+    }
+
+    fn refresh(&mut self, ctx: &egui::Context) {
+        if let Some(pending_scan) = &self.pending_scan {
+            match pending_scan.try_recv() {
+                Ok(Some(scan)) => {
+                    self.materials = scan.materials;
+                    self.directory_times = scan.directory_times;
+                }
+                Ok(None) => {}
+                Err(mpsc::TryRecvError::Empty) => return,
+                Err(mpsc::TryRecvError::Disconnected) => {}
+            }
+            self.pending_scan = None;
+        }
+
+        if self
+            .last_scan
+            .is_some_and(|last_scan| last_scan.elapsed() < WORKSPACE_SCAN_INTERVAL)
+        {
+            return;
+        }
+
+        self.last_scan = Some(Instant::now());
+        let (sender, receiver) = mpsc::channel();
+        let directory = self.directory.clone();
+        let known_directory_times = self.directory_times.clone();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            // Only reading directory metadata, the material tomls are read again once something changed
+            let mut directory_times = Vec::new();
+            collect_directory_times(&directory, &mut directory_times);
+            if directory_times == known_directory_times {
+                let _ = sender.send(None);
+                return;
+            }
+
+            let scan = Scan {
+                materials: material::find_material_files(&directory),
+                directory_times,
+            };
+            // The workspace may have changed directory meanwhile, then nobody is listening
+            if sender.send(Some(scan)).is_ok() {
+                ctx.request_repaint();
+            }
+        });
+        self.pending_scan = Some(receiver);
+    }
+
+    /// Lists the materials, clicking one opens it. Materials open in a tab are shown in bold.
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        open_materials: &[&Path],
+        active_material: &Path,
+    ) -> WorkspaceAction {
+        self.refresh(ui.ctx());
+        let mut action = WorkspaceAction::None;
+
+        ui.horizontal(|ui| {
+            ui.heading("Workspace");
+            if ui
+                .small_button("...")
+                .on_hover_text("Choose the workspace directory")
+                .clicked()
+            {
+                action = WorkspaceAction::ChooseDirectory;
+            }
+        });
+        ui.label(
+            RichText::new(self.directory.to_string_lossy())
+                .small()
+                .weak(),
+        );
+        ui.add(egui::TextEdit::singleline(&mut self.filter).hint_text("Filter"));
+        ui.separator();
+
+        let filter = self.filter.to_lowercase();
+        ScrollArea::vertical()
+            .id_salt("workspace_scroll")
+            .show(ui, |ui| {
+                let mut shown = 0;
+                for material_path in &self.materials {
+                    let relative_path = material_path
+                        .strip_prefix(&self.directory)
+                        .unwrap_or(material_path)
+                        .to_string_lossy();
+                    if !relative_path.to_lowercase().contains(&filter) {
+                        continue;
+                    }
+                    shown += 1;
+
+                    let mut text = RichText::new(relative_path);
+                    if open_materials.contains(&material_path.as_path()) {
+                        text = text.strong();
+                    }
+                    if ui
+                        .selectable_label(material_path == active_material, text)
+                        .on_hover_text(material_path.to_string_lossy())
+                        .clicked()
+                    {
+                        action = WorkspaceAction::Open(material_path.clone());
+                    }
+                }
+
+                if shown == 0 && self.pending_scan.is_some() && self.materials.is_empty() {
+                    ui.weak("Scanning...");
+                } else if shown == 0 {
+                    ui.weak("No materials found");
+                }
+            });
+
+        action
+    }
+}
+
+fn collect_directory_times(directory: &Path, directory_times: &mut DirectoryTimes) {
+    let modified = fs::metadata(directory).and_then(|metadata| metadata.modified());
+    directory_times.push((directory.to_path_buf(), modified.ok()));
+
+    let Ok(dir_entries) = fs::read_dir(directory) else {
+        return;
+    };
+    for path in dir_entries.filter_map(|dir_entry| Some(dir_entry.ok()?.path())) {
+        if path.is_dir() && !material::is_skipped_directory(&path) {
+            collect_directory_times(&path, directory_times);
+        }
+    }
+}
@@ -1,12 +1,16 @@
 use std::{
+    collections::{HashMap, VecDeque},
     ffi::CString,
-    fs::{self, OpenOptions},
-    path::Path,
+    fs::{self, File, OpenOptions},
+    io::Read,
+    path::{Component as PathComponent, Path, PathBuf},
     process::Command,
     sync::{
         Mutex,
         atomic::{AtomicBool, Ordering},
     },
+    task::{Context, Poll, Waker},
+    time::{Duration, Instant, SystemTime},
 };
 
 use game_asset::{
@@ -34,6 +38,16 @@ use void_public::{
 const CAMERA_ZOOM_SPEED: f32 = 2.;
 const CAMERA_MOVE_SPEED: f32 = 200.;
 const MAX_ZOOM: f32 = 100.;
+const TEXTURE_POLL_INTERVAL: Duration = Duration::from_millis(500);
+const MAX_ERROR_MESSAGE_LEN: usize = 8192;
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+
+static ENGINE_ASSET_ROOT: &str = "../engine/target/debug/assets/";
+static LOCAL_ASSET_ROOT: &str = "./assets/";
+
+// Render targets pipelines can be built for, by the name the gui selects them with
+static SCENE_RENDER_TARGET: &str = "scene";
+static RENDER_TARGET_NAMES: [&str; 2] = [SCENE_RENDER_TARGET, "color"];
 
 static SHARED_MEM_FILE: Lazy<Mutex<MmapMut>> = Lazy::new(|| {
     println!("Opening shared file...");
@@ -65,19 +79,382 @@ struct UserInput {
     pub camera_movement_input: Vec2,
 }
 
+/// Marks a scene sprite materials are previewed on. The gui selects preview sprites by `index`,
+/// which stays the same whatever order the engine queries them in.
+#[repr(C)]
+#[derive(Component, Default, serde::Deserialize)]
+struct PreviewSprite {
+    pub index: u32,
+}
+
+/// What the preview sprites show when a compile fails.
+#[derive(Clone, Copy, PartialEq)]
+enum FallbackPolicy {
+    // Keep the last successfully compiled material and its parameters
+    KeepLastGood,
+    ShowBroken,
+}
+
+struct LoadedTexture {
+    id: TextureId,
+    modified: Option<SystemTime>,
+    width: u32,
+    height: u32,
+}
+
+impl LoadedTexture {
+    // Textures are uploaded as 8 bit rgba
+    fn size_in_bytes(&self) -> u64 {
+        self.width as u64 * self.height as u64 * 4
+    }
+}
+
 #[derive(ResourceWithoutSerialize)]
 struct MaterialEditor {
     material_id: MaterialId,
+
+    // Newest material compiled for each preview entity
+    editor_materials: HashMap<usize, MaterialId>,
+    // Compiles per material file name, see next_material_name
+    material_generations: HashMap<String, u32>,
+    // Every material registered by a compile, counted in material_stats while the manager holds it
+    registered_materials: Vec<MaterialId>,
+    material_stats_changed: bool,
+    fallback_policy: FallbackPolicy,
+
+    // Textures loaded on behalf of the gui, keyed by the path it sent. Watched for changes on disk
+    loaded_textures: HashMap<String, LoadedTexture>,
+    last_texture_poll: Instant,
+    loaded_textures_changed: bool,
+
+    engine_settings_reported: bool,
+
+    // Render target and sample count compiled pipelines are built for
+    pipeline_render_target: String,
+    pipeline_sample_count: u32,
+    // Sample count of the scene pass the preview sprites are drawn in, queried from the engine
+    scene_sample_count: u32,
+
+    // PreviewSprite index of the sprite compiles, uniform updates and texture loads apply to
+    preview_entity: usize,
+    preview_entity_count: usize,
+    // Textures each preview sprite had in the scene, unbinding a slot puts its texture back
+    scene_textures: HashMap<usize, Vec<TextureId>>,
+
+    // Messages waiting for their turn in shared memory, one is sent per exchange
+    outgoing_commands: VecDeque<String>,
 }
 
 impl Default for MaterialEditor {
     fn default() -> Self {
         MaterialEditor {
             material_id: MaterialId(0),
+            editor_materials: HashMap::new(),
+            material_generations: HashMap::new(),
+            registered_materials: Vec::new(),
+            material_stats_changed: false,
+            fallback_policy: FallbackPolicy::KeepLastGood,
+            loaded_textures: HashMap::new(),
+            last_texture_poll: Instant::now(),
+            loaded_textures_changed: false,
+            engine_settings_reported: false,
+            pipeline_render_target: SCENE_RENDER_TARGET.to_string(),
+            pipeline_sample_count: 1,
+            scene_sample_count: 1,
+            preview_entity: 0,
+            preview_entity_count: 0,
+            scene_textures: HashMap::new(),
+            outgoing_commands: VecDeque::new(),
+        }
+    }
+}
+
+impl MaterialEditor {
+    fn watch_texture(&mut self, texture_path: &str, id: TextureId) {
+        let (width, height) = png_dimensions(texture_path).unwrap_or_default();
+        self.loaded_textures.insert(
+            texture_path.to_string(),
+            LoadedTexture {
+                id,
+                modified: texture_modified_time(texture_path),
+                width,
+                height,
+            },
+        );
+        self.loaded_textures_changed = true;
+    }
+
+    /// Re-uploads every watched texture whose file changed on disk.
+    /// Returns the (previous, new) texture ids so sprites can be rebound.
+    fn reload_changed_textures(
+        &mut self,
+        gpu_interface: &mut GpuInterface,
+        new_texture_event_writer: &EventWriter<NewTexture>,
+    ) -> Vec<(TextureId, TextureId)> {
+        let mut reloaded = Vec::new();
+        if self.last_texture_poll.elapsed() < TEXTURE_POLL_INTERVAL {
+            return reloaded;
+        }
+        self.last_texture_poll = Instant::now();
+
+        for (texture_path, loaded_texture) in self.loaded_textures.iter_mut() {
+            let modified = texture_modified_time(texture_path);
+            if modified.is_none() || modified == loaded_texture.modified {
+                continue;
+            }
+            loaded_texture.modified = modified;
+
+            // Cached textures are never re-read by path, so upload the new contents under a new id.
+            // The engine has no way to free the previous upload, it stays until the engine exits
+            let Some(id) =
+                register_texture(texture_path, false, gpu_interface, new_texture_event_writer)
+            else {
+                continue;
+            };
+
+            println!("Reloaded texture {texture_path} as tex id {id}");
+            reloaded.push((loaded_texture.id, id));
+            loaded_texture.id = id;
+            (loaded_texture.width, loaded_texture.height) =
+                png_dimensions(texture_path).unwrap_or_default();
+            self.loaded_textures_changed = true;
+            self.outgoing_commands
+                .push_back(format!("texture_reloaded##DELIM##{texture_path}##DELIM##"));
+        }
+
+        reloaded
+    }
+
+    fn register_editor_material(&mut self, material_id: MaterialId) {
+        self.editor_materials
+            .insert(self.preview_entity, material_id);
+        self.material_id = material_id;
+    }
+
+    /// Name the next compile of `file_name` is registered under, ex: `default_sprite_gen3`.
+    ///
+    /// The material manager keeps every name it has registered, so the file name gets a
+    /// `_gen<generation>` suffix counting the compiles of that file.
+    fn next_material_name(&mut self, file_name: &str) -> String {
+        let generation = self
+            .material_generations
+            .entry(file_name.to_string())
+            .or_default();
+        *generation += 1;
+
+        format!("{file_name}_gen{generation}")
+    }
+
+    // Sent whenever a compile registers a material, with how many editor materials the material
+    // manager holds. Superseded materials and their pipelines stay registered with the engine
+    fn report_material_stats(&mut self, gpu_interface: &GpuInterface) {
+        if !self.material_stats_changed {
+            return;
         }
+        self.material_stats_changed = false;
+
+        self.registered_materials.retain(|material_id| {
+            gpu_interface
+                .material_manager
+                .get_material(*material_id)
+                .is_some()
+        });
+        self.outgoing_commands.push_back(format!(
+            "material_stats##DELIM##{}##DELIM##",
+            self.registered_materials.len()
+        ));
+    }
+
+    /// Reports a failed compile, returns the material the preview should switch to if any.
+    fn compile_failed(&mut self, compile_id: &str, error: &str) -> Option<MaterialId> {
+        let keep_last_good = self.fallback_policy == FallbackPolicy::KeepLastGood
+            && self.editor_materials.contains_key(&self.preview_entity);
+
+        let error_message: String = error
+            .replace('\0', "")
+            .chars()
+            .take(MAX_ERROR_MESSAGE_LEN)
+            .collect();
+        self.outgoing_commands.push_back(format!(
+            "compile_result##DELIM##error##DELIM##{error_message}##DELIM##{compile_id}##DELIM##"
+        ));
+
+        (!keep_last_good).then(|| DefaultMaterials::MissingOrBroken.material_id())
+    }
+
+    /// Format and sample count compiled pipelines are built for.
+    fn pipeline_target(&self, gpu_resource: &GpuResource) -> (wgpu::TextureFormat, u32) {
+        let render_target_type = render_target_type(&self.pipeline_render_target)
+            .unwrap_or(RenderTargetType::ColorResolve);
+        let format = gpu_resource
+            .texture_manager
+            .get_render_target(render_target_type)
+            .texture
+            .format();
+
+        (format, self.pipeline_sample_count)
+    }
+
+    // Only pipelines matching the scene pass can draw the preview sprites
+    fn previews_pipeline_target(&self) -> bool {
+        self.pipeline_render_target == SCENE_RENDER_TARGET
+            && self.pipeline_sample_count == self.scene_sample_count
+    }
+
+    /// Sends the render targets pipelines can be built for, with the sample counts their format supports.
+    /// Until the gui picks one, pipelines are built for the scene pass.
+    fn report_render_targets(&mut self, gpu_resource: &GpuResource) {
+        // The scene pass draws into the multisampled color target and resolves into color resolve
+        self.scene_sample_count = gpu_resource
+            .texture_manager
+            .get_render_target(RenderTargetType::Color)
+            .texture
+            .sample_count();
+        self.pipeline_sample_count = self.scene_sample_count;
+
+        let mut render_targets = String::new();
+        for name in RENDER_TARGET_NAMES {
+            let Some(render_target_type) = render_target_type(name) else {
+                continue;
+            };
+            let texture = &gpu_resource
+                .texture_manager
+                .get_render_target(render_target_type)
+                .texture;
+            let format = texture.format();
+            let sample_count = if name == SCENE_RENDER_TARGET {
+                self.scene_sample_count
+            } else {
+                texture.sample_count()
+            };
+
+            // Only the counts every device guarantees, plus the one the engine already renders with
+            let format_flags = format
+                .guaranteed_format_features(gpu_resource.device.features())
+                .flags;
+            let supported_sample_counts: Vec<String> = [1, 2, 4, 8, 16]
+                .into_iter()
+                .filter(|count| {
+                    *count == 1
+                        || *count == sample_count
+                        || format_flags.sample_count_supported(*count)
+                })
+                .map(|count| count.to_string())
+                .collect();
+
+            render_targets += &format!(
+                "{name}\t{format:?}\t{sample_count}\t{}\n",
+                supported_sample_counts.join(",")
+            );
+        }
+
+        self.outgoing_commands
+            .push_back(format!("render_targets##DELIM##{render_targets}##DELIM##"));
+    }
+
+    // Lets the gui offer every preview sprite when assigning materials to them
+    fn report_preview_entities(&mut self, preview_entity_count: usize) {
+        if preview_entity_count == self.preview_entity_count {
+            return;
+        }
+        self.preview_entity_count = preview_entity_count;
+
+        self.outgoing_commands.push_back(format!(
+            "preview_entities##DELIM##{preview_entity_count}##DELIM##"
+        ));
+    }
+
+    // Sent whenever the set of editor loaded textures changes. One line per texture:
+    // `path \t tex id \t width \t height \t size in bytes`
+    fn report_loaded_textures(&mut self) {
+        if !self.loaded_textures_changed {
+            return;
+        }
+        self.loaded_textures_changed = false;
+
+        let loaded_textures = self
+            .loaded_textures
+            .iter()
+            .map(|(texture_path, loaded_texture)| {
+                format!(
+                    "{texture_path}\t{}\t{}\t{}\t{}",
+                    loaded_texture.id,
+                    loaded_texture.width,
+                    loaded_texture.height,
+                    loaded_texture.size_in_bytes()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        self.outgoing_commands.push_back(format!(
+            "loaded_textures##DELIM##{loaded_textures}##DELIM##"
+        ));
+    }
+}
+
+/// Directory scenes and textures are loaded from. Asset paths like `textures/player_front.png` are relative to it.
+fn asset_root() -> PathBuf {
+    let asset_root = if Path::new(ENGINE_ASSET_ROOT).exists() {
+        ENGINE_ASSET_ROOT
+    } else {
+        LOCAL_ASSET_ROOT
+    };
+
+    Path::new(asset_root)
+        .canonicalize()
+        .unwrap_or(asset_root.into())
+}
+
+/// Converts paths inside the asset root to the asset-relative form scenes use.
+/// Paths outside of it are kept as is.
+fn normalize_asset_path(texture_path: &str) -> String {
+    let path = Path::new(texture_path);
+    if !path.is_absolute() {
+        return texture_path.replace('\\', "/");
+    }
+
+    let Ok(path) = path.canonicalize() else {
+        return texture_path.to_string();
+    };
+
+    match path.strip_prefix(asset_root()) {
+        Ok(relative_path) => relative_path
+            .components()
+            .filter_map(|component| match component {
+                PathComponent::Normal(name) => Some(name.to_string_lossy()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("/"),
+        Err(_) => texture_path.to_string(),
     }
 }
 
+// Reads the size from the png header rather than decoding the image
+fn png_dimensions(texture_path: &str) -> Option<(u32, u32)> {
+    let mut header = [0u8; 24];
+    File::open(asset_root().join(texture_path))
+        .ok()?
+        .read_exact(&mut header)
+        .ok()?;
+
+    if header[..8] != PNG_SIGNATURE || &header[12..16] != b"IHDR" {
+        return None;
+    }
+
+    Some((
+        u32::from_be_bytes(header[16..20].try_into().ok()?),
+        u32::from_be_bytes(header[20..24].try_into().ok()?),
+    ))
+}
+
+fn texture_modified_time(texture_path: &str) -> Option<SystemTime> {
+    fs::metadata(asset_root().join(texture_path))
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
 #[system_once]
 fn initialize_module() {
     println!("Initializing Material Editor module.");
@@ -114,12 +491,7 @@ fn initialize_module() {
         .expect("Failed to start Material Editor Gui");
 
     // Load scene
-    let scene_path = "../engine/target/debug/assets/scene.json";
-    let scene_str = if Path::new(scene_path).exists() {
-        fs::read_to_string("../engine/target/debug/assets/scene.json")
-    } else {
-        fs::read_to_string("./assets/scene.json")
-    };
+    let scene_str = fs::read_to_string(asset_root().join("scene.json"));
 
     assert!(scene_str.is_ok());
 
@@ -141,11 +513,12 @@ fn update_loop(
     gpu_interface: &mut GpuInterface,
     material_editor: &mut MaterialEditor,
     gpu_resource: &mut GpuResource,
-    mut texture_query: Query<(&TextureRender, &mut MaterialParameters)>,
+    mut texture_query: Query<(&PreviewSprite, &TextureRender, &mut MaterialParameters)>,
     new_texture_event_writer: EventWriter<NewTexture>,
 ) {
     let mut new_material_id: Option<MaterialId> = None;
-    let mut new_tex_id: Option<TextureId> = None;
+    let mut new_tex_id: Option<(usize, TextureId)> = None;
+    let mut unbound_slot: Option<usize> = None;
     let mut param_update: Option<(String, Vec4)> = None;
 
     // Acquire lock if available
@@ -155,9 +528,25 @@ fn update_loop(
     }
     let mut shared_mem = shared_mem.unwrap();
 
+    let reloaded_textures =
+        material_editor.reload_changed_textures(gpu_interface, &new_texture_event_writer);
+
+    // Let the gui know where asset-relative texture paths are resolved from and what it can render to
+    if !material_editor.engine_settings_reported {
+        material_editor.engine_settings_reported = true;
+        material_editor.outgoing_commands.push_back(format!(
+            "asset_root##DELIM##{}##DELIM##",
+            asset_root().to_string_lossy()
+        ));
+        material_editor.report_render_targets(gpu_resource);
+    }
+
     let read_barrier = unsafe { &*(shared_mem.as_ptr() as *mut AtomicBool) };
     if !read_barrier.load(Ordering::Acquire) {
-        let outgoing_command = String::new();
+        let outgoing_command = material_editor
+            .outgoing_commands
+            .pop_front()
+            .unwrap_or_default();
         let incoming_message = std::str::from_utf8(&shared_mem[1..]).expect("Invalid UTF-8");
 
         if incoming_message.as_bytes()[0] != b'\0' {
@@ -179,32 +568,97 @@ fn update_loop(
 
                     // Load texture
                     "load_texture" => {
-                        let texture_path = incoming_command[1];
-                        let id = if let Some(tex) = gpu_interface
+                        let texture_path = normalize_asset_path(incoming_command[1]);
+                        let texture_path = texture_path.as_str();
+                        // Texture slot of the [texture_descs] binding this texture is assigned to
+                        let slot = incoming_command
+                            .get(2)
+                            .and_then(|slot| slot.trim().parse::<usize>().ok())
+                            .unwrap_or(0);
+                        let existing_id = gpu_interface
                             .texture_asset_manager
                             .get_texture_by_path(&texture_path.into())
+                            .map(|tex| tex.id());
+
+                        let id = if let Some(loaded_texture) =
+                            material_editor.loaded_textures.get(texture_path)
                         {
-                            tex.id()
+                            Some(loaded_texture.id)
+                        } else if let Some(id) = existing_id {
+                            material_editor.watch_texture(texture_path, id);
+                            Some(id)
                         } else {
-                            let id = gpu_interface
-                                .texture_asset_manager
-                                .register_next_texture_id();
-                            let pending_texture =
-                                PendingTexture::new(id, &texture_path.into(), false);
-                            let _ = gpu_interface
-                                .texture_asset_manager
-                                .load_texture_by_pending_texture(
-                                    &pending_texture,
-                                    &new_texture_event_writer,
-                                );
+                            let id = register_texture(
+                                texture_path,
+                                false,
+                                gpu_interface,
+                                &new_texture_event_writer,
+                            );
+                            if let Some(id) = id {
+                                material_editor.watch_texture(texture_path, id);
+                            }
                             id
                         };
 
-                        new_tex_id = Some(id);
+                        if let Some(id) = id {
+                            new_tex_id = Some((slot, id));
+                        }
+                    }
+
+                    // Fallback policy for failed compiles
+                    "set_fallback_policy" => {
+                        material_editor.fallback_policy = match incoming_command[1] {
+                            "show_broken" => FallbackPolicy::ShowBroken,
+                            _ => FallbackPolicy::KeepLastGood,
+                        };
+                    }
+
+                    // Render target and sample count to build compiled pipelines for
+                    "set_pipeline_target" => {
+                        let render_target = incoming_command[1].trim();
+                        let sample_count = incoming_command
+                            .get(2)
+                            .and_then(|sample_count| sample_count.trim().parse::<u32>().ok());
+
+                        match (render_target_type(render_target), sample_count) {
+                            (Some(_), Some(sample_count)) => {
+                                println!(
+                                    "Building material pipelines for {render_target} x{sample_count}"
+                                );
+                                material_editor.pipeline_render_target = render_target.to_string();
+                                material_editor.pipeline_sample_count = sample_count;
+                            }
+                            _ => println!(
+                                "Unknown pipeline target {render_target} {:?}",
+                                incoming_command.get(2)
+                            ),
+                        }
+                    }
+
+                    // Preview sprite the following commands apply to
+                    "set_preview_entity" => match incoming_command[1].trim().parse::<usize>() {
+                        Ok(preview_entity) => material_editor.preview_entity = preview_entity,
+                        Err(_) => {
+                            println!("Invalid preview entity {}", incoming_command[1])
+                        }
+                    },
+
+                    // Put back the scene's texture in a slot no texture is assigned to anymore
+                    "unbind_texture" => match incoming_command[1].trim().parse::<usize>() {
+                        Ok(slot) => unbound_slot = Some(slot),
+                        Err(_) => println!("Invalid texture slot {}", incoming_command[1]),
+                    },
+
+                    // Explicitly show the broken material after a failed compile
+                    "show_broken" => {
+                        new_material_id = Some(DefaultMaterials::MissingOrBroken.material_id());
                     }
 
                     // Compile material
                     "compile" => {
+                        // Echoed in compile_result so the gui knows which compile a reply is for
+                        let compile_id = incoming_command.get(7).copied().unwrap_or_default();
+
                         if let Some(_mat) = gpu_interface
                             .material_manager
                             .get_material(material_editor.material_id)
@@ -220,35 +674,80 @@ fn update_loop(
                                 compile_params[2],
                             );
 
+                            // Named after the material file the gui is editing
+                            let file_name = compile_params
+                                .get(5)
+                                .map(|material_name| material_name.trim())
+                                .filter(|material_name| !material_name.is_empty())
+                                .unwrap_or("editor_material");
+                            let material_name = material_editor.next_material_name(file_name);
+
                             // dbg!("---> {}", &toml_shader);
                             let mat_id = gpu_interface
                                 .material_manager
                                 .register_material_from_string(
                                     DEFAULT_SHADER_ID,
-                                    "test_mat",
+                                    &material_name,
                                     &toml_shader,
                                 );
 
+                            // Sent by the gui as six names, color src, dst and operation then alpha
+                            let blend_state = compile_params
+                                .get(6)
+                                .and_then(|blend_state| parse_blend_state(blend_state))
+                                .unwrap_or(wgpu::BlendState::ALPHA_BLENDING);
+
                             if let Ok(material_id) = mat_id {
-                                new_material_id = Some(material_id);
-                                let resolve_target = gpu_resource
-                                    .texture_manager
-                                    .get_render_target(RenderTargetType::ColorResolve);
+                                material_editor.registered_materials.push(material_id);
+                                material_editor.material_stats_changed = true;
+                                let (target_format, sample_count) =
+                                    material_editor.pipeline_target(gpu_resource);
 
-                                println!("Registering new material pipeline {material_id}");
+                                println!(
+                                    "Registering new material pipeline {material_id} ({material_name}) for {target_format:?} x{sample_count}"
+                                );
+                                // Catch invalid target settings instead of letting wgpu abort
+                                gpu_resource
+                                    .device
+                                    .push_error_scope(wgpu::ErrorFilter::Validation);
                                 gpu_resource.pipeline_manager.register_pipeline(
                                     material_id,
-                                    resolve_target.texture.format(),
-                                    4,
+                                    target_format,
+                                    sample_count,
                                     &gpu_resource.device,
                                     &gpu_interface.material_manager,
-                                    wgpu::BlendState::ALPHA_BLENDING,
+                                    blend_state,
                                 );
+                                let pipeline_error =
+                                    poll_ready(gpu_resource.device.pop_error_scope()).flatten();
+
+                                if let Some(pipeline_error) = pipeline_error {
+                                    println!(
+                                        "Unable to build material pipeline {material_id}: {pipeline_error}"
+                                    );
+                                    new_material_id = material_editor
+                                        .compile_failed(compile_id, &pipeline_error.to_string());
+                                } else if material_editor.previews_pipeline_target() {
+                                    new_material_id = Some(material_id);
+                                    material_editor.register_editor_material(material_id);
+                                    material_editor.outgoing_commands.push_back(format!(
+                                        "compile_result##DELIM##ok##DELIM##{material_name}##DELIM##{compile_id}##DELIM##"
+                                    ));
+                                } else {
+                                    // Built for an offscreen target, it can't draw in the scene pass
+                                    material_editor.outgoing_commands.push_back(format!(
+                                        "compile_result##DELIM##ok##DELIM##{material_name}##DELIM##{compile_id}##DELIM##"
+                                    ));
+                                }
                             } else {
                                 println!("Unable to register new material pipeline {:?}", mat_id);
-                                new_material_id =
-                                    Some(DefaultMaterials::MissingOrBroken.material_id());
+                                new_material_id = material_editor
+                                    .compile_failed(compile_id, &format!("{mat_id:?}"));
                             }
+                        } else {
+                            println!("Unable to compile, the editor material isn't registered");
+                            new_material_id = material_editor
+                                .compile_failed(compile_id, "The editor material isn't registered");
                         }
                     }
                     _ => {}
@@ -269,19 +768,36 @@ fn update_loop(
 
     shared_mem.flush().expect("Failed to flush");
 
-    // Update sprites with any new data
-    texture_query.for_each(|(_, parameters)| {
-        if let Some(material_id) = new_material_id {
+    // Update the selected preview sprite with any new data
+    let mut preview_entity_count = 0;
+    texture_query.for_each(|(preview_sprite, _, parameters)| {
+        let preview_entity = preview_sprite.index as usize;
+        let is_selected = preview_entity == material_editor.preview_entity;
+        let scene_textures = material_editor
+            .scene_textures
+            .entry(preview_entity)
+            .or_insert_with(|| parameters.textures.to_vec());
+        preview_entity_count = preview_entity_count.max(preview_entity + 1);
+
+        if let Some(slot) = unbound_slot.filter(|_| is_selected) {
+            if let (Some(texture), Some(scene_texture)) =
+                (parameters.textures.get_mut(slot), scene_textures.get(slot))
+            {
+                *texture = *scene_texture;
+            }
+        }
+
+        if let Some(material_id) = new_material_id.filter(|_| is_selected) {
             let default_material = gpu_interface
                 .material_manager
                 .get_material(material_id)
                 .unwrap();
 
             parameters.data = default_material.generate_default_material_parameters().data;
-            parameters.material_id = new_material_id.unwrap();
+            parameters.material_id = material_id;
         }
 
-        if let Some(param) = &param_update {
+        if let Some(param) = param_update.as_ref().filter(|_| is_selected) {
             let uniform = UniformValue::Vec4(param.1.into());
             if let Ok(new_param) = parameters.update_uniforms(
                 &gpu_interface.material_manager,
@@ -290,11 +806,32 @@ fn update_loop(
                 parameters.data = new_param.data;
             }
         }
-        if new_tex_id.is_some() {
-            println!("Setting new tex id {}", new_tex_id.unwrap());
-            parameters.textures[0] = new_tex_id.unwrap();
+        if let Some((slot, tex_id)) = new_tex_id.filter(|_| is_selected) {
+            if let Some(texture) = parameters.textures.get_mut(slot) {
+                println!("Setting new tex id {tex_id} in slot {slot}");
+                *texture = tex_id;
+            } else {
+                println!("Unable to set tex id {tex_id}, slot {slot} is out of range");
+            }
+        }
+
+        // Including the scene's own textures, so unbinding a slot doesn't put back stale contents
+        for (previous_id, reloaded_id) in &reloaded_textures {
+            for texture in parameters
+                .textures
+                .iter_mut()
+                .chain(scene_textures.iter_mut())
+            {
+                if texture == previous_id {
+                    *texture = *reloaded_id;
+                }
+            }
         }
     });
+
+    material_editor.report_preview_entities(preview_entity_count);
+    material_editor.report_material_stats(gpu_interface);
+    material_editor.report_loaded_textures();
 }
 
 #[system]
@@ -368,21 +905,105 @@ fn key_is_down(input_state: &InputState, key_code: KeyCode) -> bool {
     input_state.keys[key_code].pressed()
 }
 
+/// Blend factor by its gui name, ex: `one_minus_src_alpha`.
+fn parse_blend_factor(factor: &str) -> Option<wgpu::BlendFactor> {
+    Some(match factor {
+        "zero" => wgpu::BlendFactor::Zero,
+        "one" => wgpu::BlendFactor::One,
+        "src" => wgpu::BlendFactor::Src,
+        "one_minus_src" => wgpu::BlendFactor::OneMinusSrc,
+        "src_alpha" => wgpu::BlendFactor::SrcAlpha,
+        "one_minus_src_alpha" => wgpu::BlendFactor::OneMinusSrcAlpha,
+        "dst" => wgpu::BlendFactor::Dst,
+        "one_minus_dst" => wgpu::BlendFactor::OneMinusDst,
+        "dst_alpha" => wgpu::BlendFactor::DstAlpha,
+        "one_minus_dst_alpha" => wgpu::BlendFactor::OneMinusDstAlpha,
+        "src_alpha_saturated" => wgpu::BlendFactor::SrcAlphaSaturated,
+        _ => return None,
+    })
+}
+
+/// Blend component from its src factor, dst factor and operation names.
+fn parse_blend_component(names: &[&str]) -> Option<wgpu::BlendComponent> {
+    let operation = match *names.get(2)? {
+        "add" => wgpu::BlendOperation::Add,
+        "subtract" => wgpu::BlendOperation::Subtract,
+        "reverse_subtract" => wgpu::BlendOperation::ReverseSubtract,
+        "min" => wgpu::BlendOperation::Min,
+        "max" => wgpu::BlendOperation::Max,
+        _ => return None,
+    };
+
+    // wgpu rejects min and max pipelines unless both factors are one
+    if matches!(
+        operation,
+        wgpu::BlendOperation::Min | wgpu::BlendOperation::Max
+    ) {
+        return Some(wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::One,
+            dst_factor: wgpu::BlendFactor::One,
+            operation,
+        });
+    }
+
+    Some(wgpu::BlendComponent {
+        src_factor: parse_blend_factor(names.first()?)?,
+        dst_factor: parse_blend_factor(names.get(1)?)?,
+        operation,
+    })
+}
+
+/// Blend state from the six names the gui sends, color then alpha components,
+/// ex: `src_alpha one_minus_src_alpha add one one_minus_src_alpha add`.
+fn parse_blend_state(blend_state: &str) -> Option<wgpu::BlendState> {
+    let names: Vec<&str> = blend_state.split_whitespace().collect();
+    if names.len() != 6 {
+        return None;
+    }
+
+    Some(wgpu::BlendState {
+        color: parse_blend_component(&names[..3])?,
+        alpha: parse_blend_component(&names[3..])?,
+    })
+}
+
+fn render_target_type(name: &str) -> Option<RenderTargetType> {
+    match name {
+        "scene" => Some(RenderTargetType::ColorResolve),
+        "color" => Some(RenderTargetType::Color),
+        _ => None,
+    }
+}
+
+// wgpu resolves error scopes immediately on native backends, so there is nothing to wait for
+fn poll_ready<T>(future: impl Future<Output = T>) -> Option<T> {
+    let mut context = Context::from_waker(Waker::noop());
+    match std::pin::pin!(future).poll(&mut context) {
+        Poll::Ready(output) => Some(output),
+        Poll::Pending => None,
+    }
+}
+
+/// Uploads the texture at `texture_path` under a newly registered id.
+/// Returns None if the texture couldn't be loaded.
 pub fn register_texture(
     texture_path: &str,
     load_into_atlas: bool,
     gpu_interface: &mut GpuInterface,
     new_texture_event_writer: &EventWriter<NewTexture>,
-) -> TextureId {
+) -> Option<TextureId> {
     let id = gpu_interface
         .texture_asset_manager
         .register_next_texture_id();
     let pending_texture = PendingTexture::new(id, &texture_path.into(), load_into_atlas);
-    gpu_interface
+    if let Err(error) = gpu_interface
         .texture_asset_manager
         .load_texture_by_pending_texture(&pending_texture, new_texture_event_writer)
-        .unwrap();
-    id
+    {
+        println!("Unable to load texture {texture_path} {error:?}");
+        return None;
+    }
+    Some(id)
 }
 
 // This includes auto-generated C FFI code (saves you from writing it manually).